@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS todos_user_id_created_at_id_idx;
+
+CREATE INDEX IF NOT EXISTS todos_user_id_created_at_idx ON todos (user_id, created_at DESC);
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS todos_user_id_due_at_idx;
+
+ALTER TABLE todos
+    DROP CONSTRAINT IF EXISTS todos_remind_at_before_due_at,
+    DROP COLUMN IF EXISTS remind_at,
+    DROP COLUMN IF EXISTS priority,
+    DROP COLUMN IF EXISTS due_at;
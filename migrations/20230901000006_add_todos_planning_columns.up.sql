@@ -0,0 +1,7 @@
+ALTER TABLE todos
+    ADD COLUMN IF NOT EXISTS due_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS priority TEXT CHECK (priority IN ('low', 'medium', 'high', 'urgent')),
+    ADD COLUMN IF NOT EXISTS remind_at TIMESTAMPTZ,
+    ADD CONSTRAINT todos_remind_at_before_due_at CHECK (remind_at IS NULL OR due_at IS NULL OR remind_at <= due_at);
+
+CREATE INDEX IF NOT EXISTS todos_user_id_due_at_idx ON todos (user_id, due_at);
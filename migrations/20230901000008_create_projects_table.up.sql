@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS projects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    is_inbox BOOLEAN NOT NULL DEFAULT FALSE,
+    archived_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT projects_inbox_not_archived CHECK (NOT is_inbox OR archived_at IS NULL)
+);
+
+CREATE INDEX IF NOT EXISTS projects_user_id_idx ON projects (user_id);
+CREATE UNIQUE INDEX IF NOT EXISTS projects_user_id_inbox_idx ON projects (user_id) WHERE is_inbox;
+
+INSERT INTO projects (user_id, name, is_inbox)
+SELECT id, 'Inbox', TRUE FROM users;
+
+ALTER TABLE todos ADD COLUMN IF NOT EXISTS project_id UUID REFERENCES projects (id);
+
+UPDATE todos
+SET project_id = projects.id
+FROM projects
+WHERE projects.user_id = todos.user_id AND projects.is_inbox;
+
+ALTER TABLE todos ALTER COLUMN project_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS todos_project_id_idx ON todos (project_id);
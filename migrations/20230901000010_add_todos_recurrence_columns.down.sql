@@ -0,0 +1,11 @@
+DROP INDEX IF EXISTS todos_previous_occurrence_id_idx;
+
+ALTER TABLE todos
+    DROP CONSTRAINT IF EXISTS todos_recurrence_requires_due_at,
+    DROP COLUMN IF EXISTS previous_occurrence_id,
+    DROP COLUMN IF EXISTS occurrence,
+    DROP COLUMN IF EXISTS recurrence_count,
+    DROP COLUMN IF EXISTS recurrence_until,
+    DROP COLUMN IF EXISTS recurrence_weekdays,
+    DROP COLUMN IF EXISTS recurrence_interval,
+    DROP COLUMN IF EXISTS recurrence_frequency;
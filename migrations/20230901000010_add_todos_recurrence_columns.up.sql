@@ -0,0 +1,12 @@
+ALTER TABLE todos
+    ADD COLUMN IF NOT EXISTS recurrence_frequency TEXT CHECK (recurrence_frequency IN ('daily', 'weekly', 'monthly', 'yearly')),
+    ADD COLUMN IF NOT EXISTS recurrence_interval INTEGER NOT NULL DEFAULT 1 CHECK (recurrence_interval >= 1),
+    ADD COLUMN IF NOT EXISTS recurrence_weekdays TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS recurrence_until TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS recurrence_count INTEGER CHECK (recurrence_count >= 1),
+    ADD COLUMN IF NOT EXISTS occurrence INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS previous_occurrence_id UUID REFERENCES todos (id) ON DELETE SET NULL,
+    ADD CONSTRAINT todos_recurrence_requires_due_at CHECK (recurrence_frequency IS NULL OR due_at IS NOT NULL);
+
+-- Completing an occurrence spawns at most one successor, even when it is completed again.
+CREATE UNIQUE INDEX IF NOT EXISTS todos_previous_occurrence_id_idx ON todos (previous_occurrence_id);
@@ -0,0 +1,6 @@
+ALTER TABLE users
+    DROP COLUMN IF EXISTS locked_until,
+    DROP COLUMN IF EXISTS lockouts,
+    DROP COLUMN IF EXISTS failed_login_attempts;
+
+DROP TABLE IF EXISTS login_attempts;
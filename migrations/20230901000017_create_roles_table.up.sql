@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS roles (
+    name TEXT PRIMARY KEY,
+    permissions TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO roles (name, permissions)
+VALUES
+    ('user', '{}'),
+    ('admin', '{users:read,users:manage,todos:read_any}')
+ON CONFLICT (name) DO NOTHING;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS role TEXT NOT NULL DEFAULT 'user'
+        REFERENCES roles (name) ON UPDATE CASCADE,
+    ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMPTZ;
@@ -0,0 +1,106 @@
+#![allow(clippy::needless_return)]
+use super::dtos::{GetUsersQueryParam, PathUuid};
+use super::service;
+use crate::config::{
+    authenticated_user::AuthenticatedUser,
+    permission::{Permission, RequirePermission},
+    token_revocation::TokenRevocation,
+};
+use crate::todos::dtos::GetTodosQueryParam;
+use actix_web::{get, post, web, Responder};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    tag = "Admin",
+    path = "/api/admin/users",
+    params(GetUsersQueryParam),
+    responses(
+        (status = 200, description = "Users fetched successfully"),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Missing users:read permission", body = ErrorResponse),
+    ),
+)]
+#[get("/users", wrap = "RequirePermission::new(Permission::ReadUsers)")]
+pub async fn get_users(
+    pool: web::Data<Pool<Postgres>>,
+    query: web::Query<GetUsersQueryParam>,
+) -> impl Responder {
+    return service::get_users(pool, query).await;
+}
+
+#[utoipa::path(
+    tag = "Admin",
+    path = "/api/admin/users/{id}/disable",
+    params(("id", description = "Unique storage id of User")),
+    responses(
+        (status = 200, description = "User disabled and signed out from all sessions"),
+        (status = 400, description = "Admins cannot disable their own account", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Missing users:manage permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[post(
+    "/users/{id}/disable",
+    wrap = "RequirePermission::new(Permission::ManageUsers)"
+)]
+pub async fn disable_user(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::disable_user(pool, token_revocation, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Admin",
+    path = "/api/admin/users/{id}/enable",
+    params(("id", description = "Unique storage id of User")),
+    responses(
+        (status = 200, description = "User enabled successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Missing users:manage permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[post(
+    "/users/{id}/enable",
+    wrap = "RequirePermission::new(Permission::ManageUsers)"
+)]
+pub async fn enable_user(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::enable_user(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Admin",
+    path = "/api/admin/users/{id}/todos",
+    params(
+        ("id", description = "Unique storage id of User"),
+        GetTodosQueryParam
+    ),
+    responses(
+        (status = 200, description = "Todos of the user fetched successfully"),
+        (status = 400, description = "Invalid pagination, filters or cursor", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Missing todos:read_any permission", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+)]
+#[get(
+    "/users/{id}/todos",
+    wrap = "RequirePermission::new(Permission::ReadAnyTodos)"
+)]
+pub async fn get_user_todos(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    query: web::Query<GetTodosQueryParam>,
+) -> impl Responder {
+    return service::get_user_todos(pool, authenticated_user, path, query).await;
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Uuid, FromRow};
+use utoipa::IntoParams;
+use validator::Validate;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathUuid {
+    pub id: Uuid,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams, Validate)]
+pub struct GetUsersQueryParam {
+    #[validate(range(min = 1, max = 100, message = "limit must be between 1 and 100"))]
+    #[serde(default = "limit_default")]
+    pub limit: i64,
+    #[validate(range(min = 0, message = "offset must not be negative"))]
+    #[serde(default = "offset_default")]
+    pub offset: i64,
+    /// Only users whose email contains this text, case insensitive
+    pub search: Option<String>,
+}
+
+impl GetUsersQueryParam {
+    pub fn search_pattern(&self) -> Option<String> {
+        return self
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|search| !search.is_empty())
+            .map(|search| format!("%{}%", search));
+    }
+}
+
+/// Account details shown to administrators.
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct AdminUser {
+    pub id: Uuid,
+    pub email: String,
+    pub role: String,
+    #[serde(rename = "emailVerifiedAt")]
+    pub email_verified_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lockedUntil")]
+    pub locked_until: Option<DateTime<Utc>>,
+    #[serde(rename = "disabledAt")]
+    pub disabled_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetUsersSuccess {
+    pub users: Vec<AdminUser>,
+    pub total: i64,
+    pub page: i64,
+    #[serde(rename = "perPage")]
+    pub per_page: i64,
+    #[serde(rename = "totalPages")]
+    pub total_pages: i64,
+}
+
+const fn limit_default() -> i64 {
+    const LIMIT_DEFAULT: i64 = 10;
+    return LIMIT_DEFAULT;
+}
+const fn offset_default() -> i64 {
+    const OFFSET_DEFAULT: i64 = 0;
+    return OFFSET_DEFAULT;
+}
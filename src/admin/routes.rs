@@ -0,0 +1,15 @@
+use crate::config::{jwt::JWTAuthentication, rate_limit::RateLimit};
+
+use super::controllers::{disable_user, enable_user, get_user_todos, get_users};
+use actix_web::web;
+
+pub fn scoped_config(service_config: &mut web::ServiceConfig) {
+    let admin_scope = web::scope("/admin")
+        .wrap(RateLimit::per_user())
+        .wrap(JWTAuthentication {})
+        .service(get_users)
+        .service(disable_user)
+        .service(enable_user)
+        .service(get_user_todos);
+    service_config.service(admin_scope);
+}
@@ -0,0 +1,170 @@
+use actix_web::{http::StatusCode, web, HttpResponse};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::{authenticated_user::AuthenticatedUser, token_revocation::TokenRevocation},
+    error::AppError,
+    todos::{self, dtos::GetTodosQueryParam},
+    users::service::revoke_all_sessions,
+};
+
+use super::dtos::{AdminUser, GetUsersQueryParam, GetUsersSuccess, PathUuid};
+
+fn user_not_found(user_id: Uuid) -> AppError {
+    return AppError::NotFound(format!("user with id {} not found", user_id));
+}
+
+pub async fn get_users(
+    pool: web::Data<Pool<Postgres>>,
+    query: web::Query<GetUsersQueryParam>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+    let pool = pool.as_ref();
+    let search_pattern = query.search_pattern();
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM users
+        WHERE $1::TEXT IS NULL OR email ILIKE $1
+        "#,
+        search_pattern
+    )
+    .fetch_one(pool)
+    .await?;
+    let users = sqlx::query_as!(
+        AdminUser,
+        r#"
+        SELECT id, email, role, email_verified_at, locked_until, disabled_at,
+            created_at, updated_at
+        FROM users
+        WHERE $1::TEXT IS NULL OR email ILIKE $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        OFFSET $3
+        "#,
+        search_pattern,
+        query.limit,
+        query.offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let data = GetUsersSuccess {
+        users,
+        total,
+        page: (query.offset / query.limit) + 1,
+        per_page: query.limit,
+        total_pages: (total + query.limit - 1) / query.limit,
+    };
+    let json_users = json!({
+        "data": data,
+        "message": "users fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_users).unwrap());
+    return Ok(HttpResponse::Ok().json(json_users));
+}
+
+/// Disables the account and signs it out everywhere. Disabled users can
+/// neither sign in nor refresh their tokens.
+pub async fn disable_user(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = path.id;
+    if user_id == authenticated_user.id {
+        return Err(AppError::BadRequest(String::from(
+            "you cannot disable your own account",
+        )));
+    }
+    let pool = pool.as_ref();
+    let user = sqlx::query_as!(
+        AdminUser,
+        r#"
+        UPDATE users
+        SET disabled_at = COALESCE(disabled_at, NOW()), updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, email, role, email_verified_at, locked_until, disabled_at,
+            created_at, updated_at
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| user_not_found(user_id))?;
+    revoke_all_sessions(pool, token_revocation.as_ref(), user_id).await?;
+
+    let json_user = json!({
+        "data": user,
+        "message": "user disabled successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    warn!(
+        "user {} disabled by admin {}",
+        user_id, authenticated_user.id
+    );
+    return Ok(HttpResponse::Ok().json(json_user));
+}
+
+pub async fn enable_user(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = path.id;
+    let user = sqlx::query_as!(
+        AdminUser,
+        r#"
+        UPDATE users
+        SET disabled_at = NULL, updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, email, role, email_verified_at, locked_until, disabled_at,
+            created_at, updated_at
+        "#,
+        user_id
+    )
+    .fetch_optional(pool.as_ref())
+    .await?
+    .ok_or_else(|| user_not_found(user_id))?;
+
+    let json_user = json!({
+        "data": user,
+        "message": "user enabled successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!(
+        "user {} enabled by admin {}",
+        user_id, authenticated_user.id
+    );
+    return Ok(HttpResponse::Ok().json(json_user));
+}
+
+pub async fn get_user_todos(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    query: web::Query<GetTodosQueryParam>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = path.id;
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(SELECT 1 FROM users WHERE id = $1) AS "exists!"
+        "#,
+        user_id
+    )
+    .fetch_one(pool.as_ref())
+    .await?;
+    if !exists {
+        return Err(user_not_found(user_id));
+    }
+    info!(
+        "admin {} viewing todos of user {}",
+        authenticated_user.id, user_id
+    );
+    return todos::service::get_todos(pool, user_id, query).await;
+}
@@ -0,0 +1,34 @@
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Identity of the caller, inserted into the request extensions by
+/// `JWTAuthentication`. Extracting it outside of a protected scope yields 401.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub id: Uuid,
+    pub jti: Uuid,
+    pub exp: usize,
+    /// Whether the caller signed in with a personal access token instead of a
+    /// JWT, in which case `jti` is the id of that token
+    pub personal_access_token: bool,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let authenticated_user = request
+            .extensions()
+            .get::<AuthenticatedUser>()
+            .cloned()
+            .ok_or_else(|| {
+                AppError::Unauthorized(String::from("missing or invalid authorization header"))
+            });
+        return ready(authenticated_user);
+    }
+}
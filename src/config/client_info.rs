@@ -0,0 +1,46 @@
+use std::future::{ready, Ready};
+
+use actix_web::{dev::Payload, http::header::USER_AGENT, FromRequest, HttpRequest};
+
+use crate::error::AppError;
+
+use super::environment::EnvironmentVariables;
+
+/// Network details of the caller, recorded alongside sign-in attempts.
+#[derive(Clone, Debug)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    /// Address of the caller. `Forwarded` and `X-Forwarded-For` can be forged by
+    /// any client, so they are only honoured when `TRUST_FORWARDED_FOR` is set.
+    pub fn ip_address(request: &HttpRequest) -> Option<String> {
+        let environment_variables = EnvironmentVariables::initialize();
+        let connection_info = request.connection_info();
+        let ip_address = if environment_variables.trust_forwarded_for {
+            connection_info.realip_remote_addr()
+        } else {
+            connection_info.peer_addr()
+        };
+        return ip_address.map(str::to_owned);
+    }
+}
+
+impl FromRequest for ClientInfo {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(request: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let user_agent = request
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .map(str::to_owned);
+        return ready(Ok(ClientInfo {
+            ip_address: ClientInfo::ip_address(request),
+            user_agent,
+        }));
+    }
+}
@@ -5,7 +5,14 @@ use super::environment::EnvironmentVariables;
 pub async fn get_pool() -> Pool<Postgres> {
     let environment_variables = EnvironmentVariables::initialize();
     let url = environment_variables.database_url.as_str();
-    return sqlx::postgres::PgPool::connect(url)
+    let pool = sqlx::postgres::PgPool::connect(url)
         .await
         .expect("Could Not Connect To Database");
+    if environment_variables.database_run_migrations {
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Could Not Run Database Migrations");
+    }
+    return pool;
 }
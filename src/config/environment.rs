@@ -1,12 +1,42 @@
 use dotenv;
 
+use super::rate_limit::RateLimitStoreKind;
+
 #[derive(Debug)]
 pub struct EnvironmentVariables {
     pub web_server_protocol: String,
     pub web_server_host: String,
     pub web_server_port: String,
     pub database_url: String,
+    pub database_run_migrations: bool,
     pub jwt_secret: String,
+    pub jwt_access_token_lifetime_minutes: i64,
+    pub refresh_token_lifetime_days: i64,
+    pub token_revocation_cache_seconds: u64,
+    pub bulk_max_operations: usize,
+    pub trash_retention_days: i64,
+    pub trash_purge_interval_minutes: u64,
+    pub json_payload_limit_bytes: usize,
+    pub rate_limit_store: RateLimitStoreKind,
+    pub trust_forwarded_for: bool,
+    pub rate_limit_ip_burst: u32,
+    pub rate_limit_ip_per_minute: u32,
+    pub rate_limit_user_burst: u32,
+    pub rate_limit_user_per_minute: u32,
+    pub login_lockout_threshold: i32,
+    pub login_lockout_minutes: i64,
+    pub login_lockout_max_minutes: i64,
+    pub mailer: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub mail_from: String,
+    pub email_verification_url: Option<String>,
+    pub email_verification_token_lifetime_hours: i64,
+    pub require_email_verification: bool,
+    pub password_reset_url: Option<String>,
+    pub password_reset_token_lifetime_minutes: i64,
 }
 
 impl EnvironmentVariables {
@@ -16,13 +46,193 @@ impl EnvironmentVariables {
         let web_server_host = dotenv::var("WEB_SERVER_HOST").expect("WEB_SERVER_HOST must be set");
         let web_server_port = dotenv::var("WEB_SERVER_PORT").expect("WEB_SERVER_PORT must be set");
         let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let database_run_migrations = dotenv::var("DATABASE_RUN_MIGRATIONS")
+            .map(|value| {
+                value
+                    .parse::<bool>()
+                    .expect("DATABASE_RUN_MIGRATIONS must be a boolean")
+            })
+            .unwrap_or(true);
         let jwt_secret = dotenv::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        let jwt_access_token_lifetime_minutes = dotenv::var("JWT_ACCESS_TOKEN_LIFETIME_MINUTES")
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .expect("JWT_ACCESS_TOKEN_LIFETIME_MINUTES must be a number")
+            })
+            .unwrap_or(60);
+        let refresh_token_lifetime_days = dotenv::var("REFRESH_TOKEN_LIFETIME_DAYS")
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .expect("REFRESH_TOKEN_LIFETIME_DAYS must be a number")
+            })
+            .unwrap_or(30);
+        let token_revocation_cache_seconds = dotenv::var("TOKEN_REVOCATION_CACHE_SECONDS")
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .expect("TOKEN_REVOCATION_CACHE_SECONDS must be a number")
+            })
+            .unwrap_or(30);
+        let bulk_max_operations = dotenv::var("BULK_MAX_OPERATIONS")
+            .map(|value| {
+                value
+                    .parse::<usize>()
+                    .expect("BULK_MAX_OPERATIONS must be a number")
+            })
+            .unwrap_or(100);
+        let trash_retention_days = dotenv::var("TRASH_RETENTION_DAYS")
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .expect("TRASH_RETENTION_DAYS must be a number")
+            })
+            .unwrap_or(30);
+        let trash_purge_interval_minutes = dotenv::var("TRASH_PURGE_INTERVAL_MINUTES")
+            .map(|value| {
+                value
+                    .parse::<u64>()
+                    .expect("TRASH_PURGE_INTERVAL_MINUTES must be a number")
+            })
+            .unwrap_or(60);
+        let json_payload_limit_bytes = dotenv::var("JSON_PAYLOAD_LIMIT_BYTES")
+            .map(|value| {
+                value
+                    .parse::<usize>()
+                    .expect("JSON_PAYLOAD_LIMIT_BYTES must be a number")
+            })
+            .unwrap_or(262_144);
+        let rate_limit_store = dotenv::var("RATE_LIMIT_STORE")
+            .map(|value| {
+                RateLimitStoreKind::parse(&value)
+                    .expect("RATE_LIMIT_STORE must be memory or postgres")
+            })
+            .unwrap_or(RateLimitStoreKind::Memory);
+        let trust_forwarded_for = dotenv::var("TRUST_FORWARDED_FOR")
+            .map(|value| {
+                value
+                    .parse::<bool>()
+                    .expect("TRUST_FORWARDED_FOR must be a boolean")
+            })
+            .unwrap_or(false);
+        let rate_limit_ip_burst = dotenv::var("RATE_LIMIT_IP_BURST")
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .expect("RATE_LIMIT_IP_BURST must be a number")
+            })
+            .unwrap_or(10);
+        let rate_limit_ip_per_minute = dotenv::var("RATE_LIMIT_IP_PER_MINUTE")
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .expect("RATE_LIMIT_IP_PER_MINUTE must be a number")
+            })
+            .unwrap_or(10);
+        let rate_limit_user_burst = dotenv::var("RATE_LIMIT_USER_BURST")
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .expect("RATE_LIMIT_USER_BURST must be a number")
+            })
+            .unwrap_or(60);
+        let rate_limit_user_per_minute = dotenv::var("RATE_LIMIT_USER_PER_MINUTE")
+            .map(|value| {
+                value
+                    .parse::<u32>()
+                    .expect("RATE_LIMIT_USER_PER_MINUTE must be a number")
+            })
+            .unwrap_or(120);
+        let login_lockout_threshold = dotenv::var("LOGIN_LOCKOUT_THRESHOLD")
+            .map(|value| {
+                value
+                    .parse::<i32>()
+                    .expect("LOGIN_LOCKOUT_THRESHOLD must be a number")
+            })
+            .unwrap_or(5);
+        let login_lockout_minutes = dotenv::var("LOGIN_LOCKOUT_MINUTES")
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .expect("LOGIN_LOCKOUT_MINUTES must be a number")
+            })
+            .unwrap_or(15);
+        let login_lockout_max_minutes = dotenv::var("LOGIN_LOCKOUT_MAX_MINUTES")
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .expect("LOGIN_LOCKOUT_MAX_MINUTES must be a number")
+            })
+            .unwrap_or(1440);
+        let mailer = dotenv::var("MAILER").unwrap_or_else(|_| String::from("log"));
+        let smtp_host = dotenv::var("SMTP_HOST").ok();
+        let smtp_port = dotenv::var("SMTP_PORT")
+            .map(|value| value.parse::<u16>().expect("SMTP_PORT must be a number"))
+            .unwrap_or(587);
+        let smtp_username = dotenv::var("SMTP_USERNAME").ok();
+        let smtp_password = dotenv::var("SMTP_PASSWORD").ok();
+        let mail_from =
+            dotenv::var("MAIL_FROM").unwrap_or_else(|_| String::from("no-reply@localhost"));
+        let email_verification_url = dotenv::var("EMAIL_VERIFICATION_URL").ok();
+        let email_verification_token_lifetime_hours =
+            dotenv::var("EMAIL_VERIFICATION_TOKEN_LIFETIME_HOURS")
+                .map(|value| {
+                    value
+                        .parse::<i64>()
+                        .expect("EMAIL_VERIFICATION_TOKEN_LIFETIME_HOURS must be a number")
+                })
+                .unwrap_or(24);
+        let require_email_verification = dotenv::var("REQUIRE_EMAIL_VERIFICATION")
+            .map(|value| {
+                value
+                    .parse::<bool>()
+                    .expect("REQUIRE_EMAIL_VERIFICATION must be a boolean")
+            })
+            .unwrap_or(false);
+        let password_reset_url = dotenv::var("PASSWORD_RESET_URL").ok();
+        let password_reset_token_lifetime_minutes =
+            dotenv::var("PASSWORD_RESET_TOKEN_LIFETIME_MINUTES")
+                .map(|value| {
+                    value
+                        .parse::<i64>()
+                        .expect("PASSWORD_RESET_TOKEN_LIFETIME_MINUTES must be a number")
+                })
+                .unwrap_or(60);
         return EnvironmentVariables {
             web_server_protocol,
             web_server_host,
             web_server_port,
             database_url,
+            database_run_migrations,
             jwt_secret,
+            jwt_access_token_lifetime_minutes,
+            refresh_token_lifetime_days,
+            token_revocation_cache_seconds,
+            bulk_max_operations,
+            trash_retention_days,
+            trash_purge_interval_minutes,
+            json_payload_limit_bytes,
+            rate_limit_store,
+            trust_forwarded_for,
+            rate_limit_ip_burst,
+            rate_limit_ip_per_minute,
+            rate_limit_user_burst,
+            rate_limit_user_per_minute,
+            login_lockout_threshold,
+            login_lockout_minutes,
+            login_lockout_max_minutes,
+            mailer,
+            smtp_host,
+            smtp_port,
+            smtp_username,
+            smtp_password,
+            mail_from,
+            email_verification_url,
+            email_verification_token_lifetime_hours,
+            require_email_verification,
+            password_reset_url,
+            password_reset_token_lifetime_minutes,
         };
     }
 }
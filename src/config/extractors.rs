@@ -0,0 +1,24 @@
+use actix_web::web;
+
+use crate::error::AppError;
+
+use super::environment::EnvironmentVariables;
+
+/// Limits JSON bodies to `JSON_PAYLOAD_LIMIT_BYTES` and reports malformed,
+/// oversized or non JSON bodies with the API error envelope.
+pub fn json_config() -> web::JsonConfig {
+    let environment_variables = EnvironmentVariables::initialize();
+    return web::JsonConfig::default()
+        .limit(environment_variables.json_payload_limit_bytes)
+        .error_handler(|error, _request| AppError::from(error).into());
+}
+
+pub fn query_config() -> web::QueryConfig {
+    return web::QueryConfig::default()
+        .error_handler(|error, _request| AppError::from(error).into());
+}
+
+pub fn path_config() -> web::PathConfig {
+    return web::PathConfig::default()
+        .error_handler(|error, _request| AppError::from(error).into());
+}
@@ -0,0 +1,105 @@
+use serde_json::{Map, Value};
+
+/// JSON Merge Patch as described in RFC 7396.
+pub struct JsonMergePatch {}
+
+impl JsonMergePatch {
+    /// Applies `patch` to `target`. Members set to `null` in the patch are
+    /// removed, nested objects are merged and any other value replaces the
+    /// target outright.
+    pub fn apply(target: &mut Value, patch: Value) {
+        let Value::Object(patch) = patch else {
+            *target = patch;
+            return;
+        };
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        let Some(target) = target.as_object_mut() else {
+            return;
+        };
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(&key);
+            } else {
+                JsonMergePatch::apply(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::JsonMergePatch;
+
+    fn merge(mut target: Value, patch: Value) -> Value {
+        JsonMergePatch::apply(&mut target, patch);
+        return target;
+    }
+
+    /// Example test cases from RFC 7396 Appendix A.
+    #[test]
+    fn rfc_7396_appendix_a() {
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": "b", "b": "c"}),
+                json!({"a": null}),
+                json!({"b": "c"}),
+            ),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!([1, 2]),
+                json!({"a": "b", "c": null}),
+                json!({"a": "b"}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ];
+        for (target, patch, expected) in cases {
+            let description = format!("{} patched with {}", target, patch);
+            assert_eq!(merge(target, patch), expected, "{}", description);
+        }
+    }
+
+    #[test]
+    fn nested_merge_keeps_untouched_members() {
+        let target = json!({"title": "a", "recurrence": {"frequency": "daily", "interval": 2}});
+        let patch = json!({"recurrence": {"interval": 3}});
+        assert_eq!(
+            merge(target, patch),
+            json!({"title": "a", "recurrence": {"frequency": "daily", "interval": 3}})
+        );
+    }
+}
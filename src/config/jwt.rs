@@ -1,37 +1,57 @@
-use super::environment::EnvironmentVariables;
+use super::{
+    authenticated_user::AuthenticatedUser, environment::EnvironmentVariables,
+    personal_access_token::PersonalAccessToken, token_revocation::TokenRevocation,
+};
+use crate::error::AppError;
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct JWT {}
 use actix_web::{
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    error::ErrorUnauthorized,
-    http::StatusCode,
-    Error, HttpMessage,
+    web, Error, HttpMessage,
 };
 use chrono::{Duration, Utc};
 use futures_util::future::LocalBoxFuture;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::future::{ready, Ready};
+use sqlx::{Pool, Postgres};
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
-    pub iat: usize,
+    pub jti: String,
+    /// Seconds with microsecond precision, so that revoking every token of a
+    /// user also catches tokens issued earlier in the same second
+    pub iat: f64,
     pub exp: usize,
+    /// Tokens issued before roles existed carry no role. Permissions are
+    /// looked up by `RequirePermission` instead of being trusted from here.
+    #[serde(default)]
+    pub role: String,
 }
 
 impl JWT {
-    pub fn jwt_encode(user_id: String) -> Result<std::string::String, jsonwebtoken::errors::Error> {
+    pub fn jwt_encode(
+        user_id: String,
+        role: String,
+    ) -> Result<std::string::String, jsonwebtoken::errors::Error> {
         let environment_variables = EnvironmentVariables::initialize();
         let jwt_secret = environment_variables.jwt_secret.as_str();
+        let lifetime = environment_variables.jwt_access_token_lifetime_minutes;
         let now = Utc::now();
-        let iat = now.timestamp() as usize;
-        let exp = (now + Duration::minutes(60)).timestamp() as usize;
+        let iat = now.timestamp_micros() as f64 / 1_000_000.0;
+        let exp = (now + Duration::minutes(lifetime)).timestamp() as usize;
         let claims: Claims = Claims {
             sub: user_id,
+            jti: Uuid::new_v4().to_string(),
             exp,
             iat,
+            role,
         };
 
         let token = encode(
@@ -55,12 +75,12 @@ impl JWT {
 }
 pub struct JWTAuthentication;
 pub struct JWTAuthenticationMiddleware<S> {
-    pub service: S,
+    pub service: Rc<S>,
 }
 
 impl<S, B> Transform<S, ServiceRequest> for JWTAuthentication
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -71,13 +91,15 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(JWTAuthenticationMiddleware { service }))
+        ready(Ok(JWTAuthenticationMiddleware {
+            service: Rc::new(service),
+        }))
     }
 }
 
 impl<S, B> Service<ServiceRequest> for JWTAuthenticationMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -92,29 +114,103 @@ where
             .headers()
             .get("Authorization")
             .and_then(|header| header.to_str().ok()?.strip_prefix("Bearer "));
-        if token.is_none() {
-            let json_error = json!({
-                "message":"missing or invalid authorization header",
-                "statusCode": StatusCode::UNAUTHORIZED.as_u16(),
-            });
-            return Box::pin(ready(Err(ErrorUnauthorized(json_error))));
+        let Some(token) = token else {
+            let error =
+                AppError::Unauthorized(String::from("missing or invalid authorization header"));
+            return Box::pin(ready(Err(error.into())));
+        };
+        if PersonalAccessToken::is_personal_access_token(token) {
+            let token = token.to_owned();
+            return self.call_with_personal_access_token(request, token);
         }
         let decode: Result<TokenData<Claims>, jsonwebtoken::errors::Error> =
-            JWT::jwt_decode(token.unwrap().to_string());
+            JWT::jwt_decode(token.to_string());
 
-        match decode {
-            Ok(TokenData { header: _, claims }) => {
-                let user_id = claims.sub;
-                request.extensions_mut().insert(user_id);
+        let claims = match decode {
+            Ok(TokenData { header: _, claims }) => claims,
+            Err(error) => {
+                return Box::pin(ready(Err(AppError::from(error).into())));
             }
-            Err(_) => {
-                let json_error = json!({
-                    "message":"invalid JWT token",
-                    "statusCode": StatusCode::UNAUTHORIZED.as_u16(),
-                });
-                return Box::pin(ready(Err(ErrorUnauthorized(json_error))));
+        };
+        let ids = Uuid::parse_str(&claims.jti).and_then(|jti| {
+            let user_id = Uuid::parse_str(&claims.sub)?;
+            return Ok((jti, user_id));
+        });
+        let Ok((jti, user_id)) = ids else {
+            let error = AppError::Unauthorized(String::from("invalid JWT token"));
+            return Box::pin(ready(Err(error.into())));
+        };
+
+        let service = Rc::clone(&self.service);
+        return Box::pin(async move {
+            let pool = request.app_data::<web::Data<Pool<Postgres>>>().cloned();
+            let token_revocation = request.app_data::<web::Data<TokenRevocation>>().cloned();
+            let (Some(pool), Some(token_revocation)) = (pool, token_revocation) else {
+                let error =
+                    AppError::internal("database pool or token revocation state is not registered");
+                return Err(error.into());
+            };
+            let revoked = token_revocation
+                .is_revoked(pool.as_ref(), jti, user_id, &claims)
+                .await
+                .map_err(AppError::from)?;
+            if revoked {
+                let error = AppError::Unauthorized(String::from("JWT token has been revoked"));
+                return Err(error.into());
             }
-        }
-        return Box::pin(self.service.call(request));
+            request.extensions_mut().insert(AuthenticatedUser {
+                id: user_id,
+                jti,
+                exp: claims.exp,
+                personal_access_token: false,
+            });
+            return service.call(request).await;
+        });
+    }
+}
+
+impl<S, B> JWTAuthenticationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    /// Authenticates a personal access token in place of a JWT. The token
+    /// has to grant the scope of the route being called.
+    fn call_with_personal_access_token(
+        &self,
+        request: ServiceRequest,
+        token: String,
+    ) -> LocalBoxFuture<'static, Result<ServiceResponse<B>, Error>> {
+        let Some(required_scope) =
+            PersonalAccessToken::required_scope(request.method(), request.path())
+        else {
+            let error = AppError::Forbidden(String::from(
+                "personal access tokens cannot be used for this endpoint",
+            ));
+            return Box::pin(ready(Err(error.into())));
+        };
+        let service = Rc::clone(&self.service);
+        return Box::pin(async move {
+            let Some(pool) = request.app_data::<web::Data<Pool<Postgres>>>().cloned() else {
+                let error = AppError::internal("database pool is not registered");
+                return Err(error.into());
+            };
+            let authenticated = PersonalAccessToken::authenticate(pool.as_ref(), &token)
+                .await
+                .map_err(AppError::from)?;
+            let Some((authenticated_user, scopes)) = authenticated else {
+                let error = AppError::Unauthorized(String::from(
+                    "invalid, expired or revoked personal access token",
+                ));
+                return Err(error.into());
+            };
+            if !PersonalAccessToken::grants(&scopes, &required_scope) {
+                let error = AppError::Forbidden(format!("missing scope {}", required_scope));
+                return Err(error.into());
+            }
+            request.extensions_mut().insert(authenticated_user);
+            return service.call(request).await;
+        });
     }
 }
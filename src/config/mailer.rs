@@ -0,0 +1,127 @@
+use std::{
+    future::ready,
+    sync::{Arc, Mutex},
+};
+
+use futures_util::future::LocalBoxFuture;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use tracing::info;
+
+use crate::error::AppError;
+
+use super::environment::EnvironmentVariables;
+
+#[derive(Clone, Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers the emails sent by the API, chosen on startup through `MAILER`.
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: Email) -> LocalBoxFuture<'_, Result<(), AppError>>;
+}
+
+/// Writes emails to the log instead of delivering them, for local development.
+pub struct LogMailer {}
+
+impl Mailer for LogMailer {
+    fn send(&self, email: Email) -> LocalBoxFuture<'_, Result<(), AppError>> {
+        info!(
+            "email to {} with subject \"{}\": {}",
+            email.to, email.subject, email.body
+        );
+        return Box::pin(ready(Ok(())));
+    }
+}
+
+/// Keeps emails in memory so that tests can read back what was sent.
+#[derive(Default)]
+pub struct MemoryMailer {
+    outbox: Mutex<Vec<Email>>,
+}
+
+impl MemoryMailer {
+    #[allow(dead_code)]
+    pub fn sent(&self) -> Vec<Email> {
+        return self.outbox.lock().unwrap().clone();
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, email: Email) -> LocalBoxFuture<'_, Result<(), AppError>> {
+        self.outbox.lock().unwrap().push(email);
+        return Box::pin(ready(Ok(())));
+    }
+}
+
+/// Delivers emails through `SMTP_HOST`, with implicit TLS on port 465 and
+/// STARTTLS on any other port.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn initialize() -> SmtpMailer {
+        let environment_variables = EnvironmentVariables::initialize();
+        let host = environment_variables
+            .smtp_host
+            .expect("SMTP_HOST must be set when MAILER is smtp");
+        let builder = if environment_variables.smtp_port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
+        };
+        let mut builder = builder
+            .expect("SMTP_HOST must be a valid host name")
+            .port(environment_variables.smtp_port);
+        if let (Some(username), Some(password)) = (
+            environment_variables.smtp_username,
+            environment_variables.smtp_password,
+        ) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        let from = environment_variables
+            .mail_from
+            .parse()
+            .expect("MAIL_FROM must be a valid mailbox");
+        return SmtpMailer {
+            transport: builder.build(),
+            from,
+        };
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: Email) -> LocalBoxFuture<'_, Result<(), AppError>> {
+        return Box::pin(async move {
+            let to = email.to.parse::<Mailbox>().map_err(AppError::internal)?;
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(email.subject)
+                .body(email.body)
+                .map_err(AppError::internal)?;
+            self.transport
+                .send(message)
+                .await
+                .map_err(AppError::internal)?;
+            return Ok(());
+        });
+    }
+}
+
+pub fn initialize_mailer() -> Arc<dyn Mailer> {
+    let environment_variables = EnvironmentVariables::initialize();
+    return match environment_variables.mailer.as_str() {
+        "log" => Arc::new(LogMailer {}),
+        "memory" => Arc::new(MemoryMailer::default()),
+        "smtp" => Arc::new(SmtpMailer::initialize()),
+        _ => panic!("MAILER must be log, memory or smtp"),
+    };
+}
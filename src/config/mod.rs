@@ -1,6 +1,17 @@
 pub mod argon2;
+pub mod authenticated_user;
+pub mod client_info;
 pub mod database;
 pub mod environment;
+pub mod extractors;
+pub mod json_merge_patch;
 pub mod jwt;
 pub mod logger;
+pub mod mailer;
+pub mod opaque_token;
 pub mod open_api;
+pub mod permission;
+pub mod personal_access_token;
+pub mod rate_limit;
+pub mod signed_token;
+pub mod token_revocation;
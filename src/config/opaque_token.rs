@@ -0,0 +1,17 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+pub struct OpaqueToken {}
+
+impl OpaqueToken {
+    pub fn generate() -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        return hex::encode(bytes);
+    }
+
+    pub fn hash(token: &str) -> String {
+        let digest = Sha256::digest(token.as_bytes());
+        return hex::encode(digest);
+    }
+}
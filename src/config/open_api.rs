@@ -1,9 +1,15 @@
+use crate::admin;
+use crate::error;
+use crate::projects;
+use crate::tags;
 use crate::todos;
 use crate::users;
 
+use utoipa::openapi::path::PathItemType;
 use utoipa::openapi::security::ApiKey;
 use utoipa::openapi::security::ApiKeyValue;
 use utoipa::openapi::security::SecurityScheme;
+use utoipa::openapi::{Content, Ref};
 use utoipa::{Modify, OpenApi};
 struct SecurityAddon;
 
@@ -17,21 +23,84 @@ impl Modify for SecurityAddon {
     }
 }
 
+/// Documents the `application/merge-patch+json` body accepted when patching a
+/// todo, which `#[utoipa::path]` can not express next to the JSON one.
+struct MergePatchAddon;
+
+impl Modify for MergePatchAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let request_body = openapi
+            .paths
+            .paths
+            .get_mut("/api/todos/{id}")
+            .and_then(|path_item| path_item.operations.get_mut(&PathItemType::Patch))
+            .and_then(|operation| operation.request_body.as_mut());
+        if let Some(request_body) = request_body {
+            request_body.content.insert(
+                String::from("application/merge-patch+json"),
+                Content::new(Ref::from_schema_name("ReplaceTodo")),
+            );
+        }
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
         todos::controllers::get_todos,
         todos::controllers::get_todo,
+        todos::controllers::get_todo_occurrences,
         todos::controllers::create_todo,
+        todos::controllers::bulk_todos,
         todos::controllers::update_todo,
+        todos::controllers::replace_todo,
         todos::controllers::delete_todo,
+        todos::controllers::get_trash,
+        todos::controllers::empty_trash,
+        todos::controllers::purge_todo,
+        todos::controllers::restore_todo,
+        todos::controllers::add_todo_tag,
+        todos::controllers::remove_todo_tag,
+        todos::controllers::get_todo_items,
+        todos::controllers::create_todo_item,
+        todos::controllers::update_todo_item,
+        todos::controllers::delete_todo_item,
+        projects::controllers::get_projects,
+        projects::controllers::get_project,
+        projects::controllers::create_project,
+        projects::controllers::update_project,
+        projects::controllers::delete_project,
+        tags::controllers::get_tags,
+        tags::controllers::get_tag,
+        tags::controllers::create_tag,
+        tags::controllers::update_tag,
+        tags::controllers::delete_tag,
         users::controllers::register_user,
+        users::controllers::verify_email,
+        users::controllers::resend_verification_email,
+        users::controllers::forgot_password,
+        users::controllers::reset_password,
         users::controllers::login_user,
+        users::controllers::refresh_token,
+        users::controllers::sign_out,
+        users::controllers::sign_out_all,
+        users::controllers::get_profile,
+        users::controllers::change_email,
+        users::controllers::change_password,
+        users::controllers::delete_account,
+        users::controllers::get_personal_access_tokens,
+        users::controllers::create_personal_access_token,
+        users::controllers::revoke_personal_access_token,
+        users::controllers::get_sessions_history,
+        admin::controllers::get_users,
+        admin::controllers::disable_user,
+        admin::controllers::enable_user,
+        admin::controllers::get_user_todos,
     ),
     components(
-        schemas(todos::dtos::CreateTodo,todos::dtos::UpdateTodo,users::dtos::RegisterUser,users::dtos::LoginUser)
+        schemas(todos::dtos::CreateTodo,todos::dtos::TodoSort,todos::dtos::SortOrder,todos::dtos::TodoPriority,todos::dtos::TagsMatch,todos::dtos::Recurrence,todos::dtos::RecurrenceFrequency,todos::dtos::RecurrenceWeekday,tags::dtos::CreateTag,tags::dtos::UpdateTag,projects::dtos::CreateProject,projects::dtos::UpdateProject,todos::dtos::UpdateTodo,todos::dtos::ReplaceTodo,todos::dtos::BulkTodos,todos::dtos::BulkMode,todos::dtos::BulkTodoOperation,todos::dtos::CreateTodoItem,todos::dtos::UpdateTodoItem,users::dtos::RegisterUser,users::dtos::LoginUser,users::dtos::RefreshToken,users::dtos::VerifyEmail,users::dtos::ResendVerificationEmail,users::dtos::ForgotPassword,users::dtos::ResetPassword,users::dtos::ChangeEmail,users::dtos::ChangePassword,users::dtos::DeleteAccount,users::dtos::CreatePersonalAccessToken,users::dtos::SignOutUser,error::ErrorResponse)
     ),
-    modifiers(&SecurityAddon),
+    modifiers(&SecurityAddon, &MergePatchAddon),
     security(
         ("api_key" = [])
     ),
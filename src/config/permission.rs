@@ -0,0 +1,134 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use super::authenticated_user::AuthenticatedUser;
+use crate::error::AppError;
+
+/// Something a role can be allowed to do. Roles list the permissions they
+/// grant by name in `roles.permissions`, so custom roles only need a row there.
+#[derive(Clone, Copy, Debug)]
+pub enum Permission {
+    ReadUsers,
+    ManageUsers,
+    ReadAnyTodos,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Permission::ReadUsers => "users:read",
+            Permission::ManageUsers => "users:manage",
+            Permission::ReadAnyTodos => "todos:read_any",
+        };
+    }
+
+    /// Permissions the current role of the user grants, none when the user
+    /// does not exist anymore.
+    async fn granted_to(pool: &Pool<Postgres>, user_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        let permissions = sqlx::query_scalar!(
+            r#"
+            SELECT roles.permissions
+            FROM users
+            JOIN roles ON roles.name = users.role
+            WHERE users.id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        return Ok(permissions.unwrap_or_default());
+    }
+}
+
+/// Middleware answering 403 unless the role of the caller grants
+/// `permission`. Has to run after `JWTAuthentication`. The role is looked up
+/// on every request, so role changes apply to tokens that are already issued.
+pub struct RequirePermission {
+    permission: Permission,
+}
+
+impl RequirePermission {
+    pub fn new(permission: Permission) -> RequirePermission {
+        return RequirePermission { permission };
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Permission,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequirePermissionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.permission,
+        }))
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let permission = self.permission;
+        let missing_permission =
+            move || AppError::Forbidden(format!("missing permission {}", permission.as_str()));
+        let authenticated_user = request.extensions().get::<AuthenticatedUser>().cloned();
+        let Some(authenticated_user) = authenticated_user else {
+            let error =
+                AppError::Unauthorized(String::from("missing or invalid authorization header"));
+            return Box::pin(ready(Err(error.into())));
+        };
+        // Personal access tokens never get the permissions of the role, the
+        // admin scope stays out of reach of scripts.
+        if authenticated_user.personal_access_token {
+            return Box::pin(ready(Err(missing_permission().into())));
+        }
+
+        let service = Rc::clone(&self.service);
+        return Box::pin(async move {
+            let Some(pool) = request.app_data::<web::Data<Pool<Postgres>>>().cloned() else {
+                let error = AppError::internal("database pool is not registered");
+                return Err(error.into());
+            };
+            let granted = Permission::granted_to(pool.as_ref(), authenticated_user.id)
+                .await
+                .map_err(AppError::from)?;
+            if !granted.iter().any(|granted| granted == permission.as_str()) {
+                return Err(missing_permission().into());
+            }
+            return service.call(request).await;
+        });
+    }
+}
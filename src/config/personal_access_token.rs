@@ -0,0 +1,94 @@
+use actix_web::http::Method;
+use sqlx::{Pool, Postgres};
+
+use super::{authenticated_user::AuthenticatedUser, opaque_token::OpaqueToken};
+
+/// Scopes a personal access token can be granted. A `write` scope also allows
+/// reading the same resource.
+pub const SCOPES: [&str; 6] = [
+    "todos:read",
+    "todos:write",
+    "projects:read",
+    "projects:write",
+    "tags:read",
+    "tags:write",
+];
+
+/// Long-lived bearer tokens for scripts and integrations. They are told apart
+/// from JWTs by their prefix, and only a hash of them is stored.
+pub struct PersonalAccessToken {}
+
+impl PersonalAccessToken {
+    pub const PREFIX: &'static str = "pat_";
+
+    pub fn generate() -> String {
+        return format!("{}{}", PersonalAccessToken::PREFIX, OpaqueToken::generate());
+    }
+
+    pub fn is_personal_access_token(token: &str) -> bool {
+        return token.starts_with(PersonalAccessToken::PREFIX);
+    }
+
+    /// Scope needed to call `method` on `path`. Routes outside of the scoped
+    /// resources, such as account management and the admin scope, need none
+    /// of them and cannot be called with a personal access token at all.
+    pub fn required_scope(method: &Method, path: &str) -> Option<String> {
+        let resource = path.strip_prefix("/api/")?.split('/').next()?;
+        if !matches!(resource, "todos" | "projects" | "tags") {
+            return None;
+        }
+        let access = if matches!(*method, Method::GET | Method::HEAD) {
+            "read"
+        } else {
+            "write"
+        };
+        return Some(format!("{}:{}", resource, access));
+    }
+
+    pub fn grants(scopes: &[String], required_scope: &str) -> bool {
+        let write_scope = required_scope.replace(":read", ":write");
+        return scopes
+            .iter()
+            .any(|scope| scope == required_scope || *scope == write_scope);
+    }
+
+    /// Looks up an active token of an enabled user and records its use.
+    /// Returns the caller together with the scopes of the token.
+    pub async fn authenticate(
+        pool: &Pool<Postgres>,
+        token: &str,
+    ) -> Result<Option<(AuthenticatedUser, Vec<String>)>, sqlx::Error> {
+        let record = sqlx::query!(
+            r#"
+            UPDATE personal_access_tokens
+            SET last_used_at = NOW()
+            FROM users
+            WHERE personal_access_tokens.token_hash = $1
+                AND personal_access_tokens.revoked_at IS NULL
+                AND (
+                    personal_access_tokens.expires_at IS NULL
+                    OR personal_access_tokens.expires_at > NOW()
+                )
+                AND users.id = personal_access_tokens.user_id
+                AND users.disabled_at IS NULL
+            RETURNING personal_access_tokens.id, personal_access_tokens.user_id,
+                personal_access_tokens.scopes, personal_access_tokens.expires_at
+            "#,
+            OpaqueToken::hash(token)
+        )
+        .fetch_optional(pool)
+        .await?;
+        return Ok(record.map(|record| {
+            let authenticated_user = AuthenticatedUser {
+                id: record.user_id,
+                jti: record.id,
+                exp: record
+                    .expires_at
+                    .map(|expires_at| expires_at.timestamp() as usize)
+                    .unwrap_or(usize::MAX),
+                personal_access_token: true,
+            };
+            return (authenticated_user, record.scopes);
+        }));
+    }
+}
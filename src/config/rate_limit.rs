@@ -0,0 +1,576 @@
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Mutex,
+    time::Duration,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+    rt, web, Error, HttpMessage, ResponseError,
+};
+use chrono::{DateTime, Utc};
+use futures_util::future::LocalBoxFuture;
+use sqlx::{Pool, Postgres};
+use tracing::{error, info};
+
+use super::{
+    authenticated_user::AuthenticatedUser, client_info::ClientInfo,
+    environment::EnvironmentVariables,
+};
+use crate::error::AppError;
+
+const MAX_MEMORY_BUCKETS: usize = 100_000;
+const BUCKET_PURGE_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Size of a token bucket and how fast it refills.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimitPolicy {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: DateTime<Utc>,
+}
+
+/// Outcome of taking a token from a bucket.
+struct RateLimitDecision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    /// Seconds until the bucket is full again
+    reset: u64,
+    /// Seconds until the next token is available, zero when one was taken
+    retry_after: u64,
+}
+
+impl RateLimitPolicy {
+    fn refill_per_second(&self) -> f64 {
+        return f64::from(self.per_minute.max(1)) / 60.0;
+    }
+
+    /// Time an empty bucket needs to fill up. Buckets left alone for longer are
+    /// full, which is the same as having no bucket at all.
+    fn full_refill(&self) -> chrono::Duration {
+        let seconds = f64::from(self.burst) / self.refill_per_second();
+        return chrono::Duration::seconds(seconds.ceil() as i64);
+    }
+
+    fn take(&self, bucket: &mut Bucket, now: DateTime<Utc>) -> RateLimitDecision {
+        let burst = f64::from(self.burst);
+        let refill_per_second = self.refill_per_second();
+        let elapsed = (now - bucket.refilled_at).num_milliseconds().max(0) as f64 / 1000.0;
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_second).min(burst);
+        bucket.refilled_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let retry_after = if allowed {
+            0.0
+        } else {
+            (1.0 - bucket.tokens) / refill_per_second
+        };
+        return RateLimitDecision {
+            allowed,
+            limit: self.burst,
+            remaining: bucket.tokens.floor() as u32,
+            reset: ((burst - bucket.tokens) / refill_per_second).ceil() as u64,
+            retry_after: retry_after.ceil() as u64,
+        };
+    }
+}
+
+impl RateLimitDecision {
+    fn insert_headers(&self, headers: &mut HeaderMap) {
+        let values = [
+            ("ratelimit-limit", u64::from(self.limit)),
+            ("ratelimit-remaining", u64::from(self.remaining)),
+            ("ratelimit-reset", self.reset),
+        ];
+        for (name, value) in values {
+            headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+        }
+        if !self.allowed {
+            headers.insert(RETRY_AFTER, HeaderValue::from(self.retry_after.max(1)));
+        }
+    }
+}
+
+/// Where buckets are kept, set through `RATE_LIMIT_STORE`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateLimitStoreKind {
+    Memory,
+    Postgres,
+}
+
+impl RateLimitStoreKind {
+    pub fn parse(value: &str) -> Option<RateLimitStoreKind> {
+        return match value {
+            "memory" => Some(RateLimitStoreKind::Memory),
+            "postgres" => Some(RateLimitStoreKind::Postgres),
+            _ => None,
+        };
+    }
+}
+
+enum RateLimitStore {
+    Memory(Mutex<HashMap<String, Bucket>>),
+    Postgres,
+}
+
+/// Token bucket rate limiter. Buckets are kept in memory, or in Postgres when
+/// `RATE_LIMIT_STORE` is `postgres` so that every instance shares the same
+/// limits at the cost of a round trip per request.
+pub struct RateLimiter {
+    store: RateLimitStore,
+    ip_policy: RateLimitPolicy,
+    user_policy: RateLimitPolicy,
+}
+
+impl RateLimiter {
+    pub fn initialize() -> RateLimiter {
+        let environment_variables = EnvironmentVariables::initialize();
+        let store = match environment_variables.rate_limit_store {
+            RateLimitStoreKind::Memory => RateLimitStore::Memory(Mutex::new(HashMap::new())),
+            RateLimitStoreKind::Postgres => RateLimitStore::Postgres,
+        };
+        return RateLimiter {
+            store,
+            ip_policy: RateLimitPolicy {
+                burst: environment_variables.rate_limit_ip_burst,
+                per_minute: environment_variables.rate_limit_ip_per_minute,
+            },
+            user_policy: RateLimitPolicy {
+                burst: environment_variables.rate_limit_user_burst,
+                per_minute: environment_variables.rate_limit_user_per_minute,
+            },
+        };
+    }
+
+    /// Deletes idle Postgres buckets every few minutes. Does nothing for the
+    /// in-memory store, which prunes itself.
+    pub fn spawn_bucket_purge(&self, pool: Pool<Postgres>) {
+        if !matches!(self.store, RateLimitStore::Postgres) {
+            return;
+        }
+        let idle = self.idle_after();
+        rt::spawn(async move {
+            let mut interval = rt::time::interval(BUCKET_PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let query_result = sqlx::query!(
+                    r#"
+                    DELETE FROM rate_limit_buckets
+                    WHERE refilled_at < $1
+                    "#,
+                    Utc::now() - idle
+                )
+                .execute(&pool)
+                .await;
+                match query_result {
+                    Ok(query_result) if query_result.rows_affected() > 0 => info!(
+                        "purged {} idle rate limit buckets",
+                        query_result.rows_affected()
+                    ),
+                    Ok(_) => {}
+                    Err(error) => error!("failed to purge rate limit buckets: {}", error),
+                }
+            }
+        });
+    }
+
+    /// How long a bucket of either policy takes to fill up again.
+    fn idle_after(&self) -> chrono::Duration {
+        return self
+            .ip_policy
+            .full_refill()
+            .max(self.user_policy.full_refill());
+    }
+
+    async fn take(
+        &self,
+        pool: &Pool<Postgres>,
+        key: &str,
+        policy: RateLimitPolicy,
+    ) -> Result<RateLimitDecision, sqlx::Error> {
+        let now = Utc::now();
+        let RateLimitStore::Memory(buckets) = &self.store else {
+            return RateLimiter::take_from_postgres(pool, key, policy, now).await;
+        };
+        return Ok(self.take_from_memory(buckets, key, policy, now));
+    }
+
+    /// Takes a token from an in-memory bucket. Once the map is full, buckets
+    /// idle long enough to be full again are dropped first.
+    fn take_from_memory(
+        &self,
+        buckets: &Mutex<HashMap<String, Bucket>>,
+        key: &str,
+        policy: RateLimitPolicy,
+        now: DateTime<Utc>,
+    ) -> RateLimitDecision {
+        let mut buckets = buckets.lock().unwrap();
+        if buckets.len() >= MAX_MEMORY_BUCKETS {
+            let idle = self.idle_after();
+            buckets.retain(|_, bucket| now - bucket.refilled_at < idle);
+        }
+        let bucket = buckets.entry(key.to_owned()).or_insert(Bucket {
+            tokens: f64::from(policy.burst),
+            refilled_at: now,
+        });
+        return policy.take(bucket, now);
+    }
+
+    /// Takes a token while holding the bucket's row lock, so that concurrent
+    /// requests from any instance are counted one after the other.
+    async fn take_from_postgres(
+        pool: &Pool<Postgres>,
+        key: &str,
+        policy: RateLimitPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<RateLimitDecision, sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        let record = sqlx::query!(
+            r#"
+            INSERT INTO rate_limit_buckets (key, tokens, refilled_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (key) DO UPDATE SET key = EXCLUDED.key
+            RETURNING tokens, refilled_at
+            "#,
+            key,
+            f64::from(policy.burst),
+            now
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        let mut bucket = Bucket {
+            tokens: record.tokens,
+            refilled_at: record.refilled_at,
+        };
+        let decision = policy.take(&mut bucket, now);
+        sqlx::query!(
+            r#"
+            UPDATE rate_limit_buckets
+            SET tokens = $2, refilled_at = $3
+            WHERE key = $1
+            "#,
+            key,
+            bucket.tokens,
+            bucket.refilled_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        return Ok(decision);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum RateLimitKey {
+    Ip,
+    User,
+}
+
+/// Middleware answering 429 once a client runs out of tokens. Every response
+/// carries `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`, and
+/// rejected ones `Retry-After`.
+pub struct RateLimit {
+    key: RateLimitKey,
+}
+
+impl RateLimit {
+    /// Limits each client IP address with the `RATE_LIMIT_IP_*` policy.
+    pub fn per_ip() -> RateLimit {
+        return RateLimit {
+            key: RateLimitKey::Ip,
+        };
+    }
+
+    /// Limits each user with the `RATE_LIMIT_USER_*` policy. Has to run after
+    /// `JWTAuthentication`, requests without a user are limited by IP instead.
+    pub fn per_user() -> RateLimit {
+        return RateLimit {
+            key: RateLimitKey::User,
+        };
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    key: RateLimitKey,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            key: self.key,
+        }))
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let key = self.key;
+        return Box::pin(async move {
+            let pool = request.app_data::<web::Data<Pool<Postgres>>>().cloned();
+            let rate_limiter = request.app_data::<web::Data<RateLimiter>>().cloned();
+            let (Some(pool), Some(rate_limiter)) = (pool, rate_limiter) else {
+                let error = AppError::internal("database pool or rate limiter is not registered");
+                return Err(error.into());
+            };
+            let user_id = request
+                .extensions()
+                .get::<AuthenticatedUser>()
+                .map(|authenticated_user| authenticated_user.id);
+            let (bucket_key, policy) = match (key, user_id) {
+                (RateLimitKey::User, Some(user_id)) => {
+                    (format!("user:{}", user_id), rate_limiter.user_policy)
+                }
+                _ => (
+                    format!(
+                        "ip:{}",
+                        ClientInfo::ip_address(request.request())
+                            .unwrap_or_else(|| String::from("unknown"))
+                    ),
+                    rate_limiter.ip_policy,
+                ),
+            };
+            let decision = rate_limiter
+                .take(pool.as_ref(), &bucket_key, policy)
+                .await
+                .map_err(AppError::from)?;
+
+            if !decision.allowed {
+                let error =
+                    AppError::TooManyRequests(String::from("too many requests, try again later"));
+                let mut response = error.error_response();
+                decision.insert_headers(response.headers_mut());
+                return Ok(request.into_response(response).map_into_right_body());
+            }
+            let mut response = service.call(request).await?;
+            decision.insert_headers(response.headers_mut());
+            return Ok(response.map_into_left_body());
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use super::{
+        Bucket, RateLimitPolicy, RateLimitStore, RateLimitStoreKind, RateLimiter,
+        MAX_MEMORY_BUCKETS,
+    };
+
+    const ONE_PER_SECOND: RateLimitPolicy = RateLimitPolicy {
+        burst: 3,
+        per_minute: 60,
+    };
+
+    fn start() -> DateTime<Utc> {
+        return Utc.with_ymd_and_hms(2023, 9, 1, 12, 0, 0).unwrap();
+    }
+
+    fn full_bucket(policy: RateLimitPolicy, now: DateTime<Utc>) -> Bucket {
+        return Bucket {
+            tokens: f64::from(policy.burst),
+            refilled_at: now,
+        };
+    }
+
+    #[test]
+    fn allows_the_burst_then_rejects() {
+        let now = start();
+        let mut bucket = full_bucket(ONE_PER_SECOND, now);
+        for remaining in [2, 1, 0] {
+            let decision = ONE_PER_SECOND.take(&mut bucket, now);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+            assert_eq!(decision.limit, 3);
+            assert_eq!(decision.retry_after, 0);
+        }
+        let decision = ONE_PER_SECOND.take(&mut bucket, now);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.retry_after, 1);
+        assert_eq!(decision.reset, 3);
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let now = start();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            refilled_at: now,
+        };
+        let decision = ONE_PER_SECOND.take(&mut bucket, now + Duration::milliseconds(500));
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, 1);
+
+        let decision = ONE_PER_SECOND.take(&mut bucket, now + Duration::milliseconds(1500));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!((bucket.tokens - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn refill_is_capped_at_the_burst() {
+        let now = start();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            refilled_at: now,
+        };
+        let decision = ONE_PER_SECOND.take(&mut bucket, now + Duration::hours(1));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.reset, 1);
+    }
+
+    #[test]
+    fn slow_refill_waits_longer() {
+        let policy = RateLimitPolicy {
+            burst: 2,
+            per_minute: 6,
+        };
+        let now = start();
+        let mut bucket = Bucket {
+            tokens: 0.25,
+            refilled_at: now,
+        };
+        let decision = policy.take(&mut bucket, now);
+        assert!(!decision.allowed);
+        // 0.75 tokens short at one token every 10 seconds
+        assert_eq!(decision.retry_after, 8);
+        assert_eq!(decision.reset, 18);
+    }
+
+    #[test]
+    fn clock_going_backwards_does_not_refill() {
+        let now = start();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            refilled_at: now,
+        };
+        let decision = ONE_PER_SECOND.take(&mut bucket, now - Duration::minutes(5));
+        assert!(!decision.allowed);
+    }
+
+    fn memory_limiter() -> RateLimiter {
+        return RateLimiter {
+            store: RateLimitStore::Memory(Mutex::new(HashMap::new())),
+            ip_policy: ONE_PER_SECOND,
+            user_policy: RateLimitPolicy {
+                burst: 10,
+                per_minute: 60,
+            },
+        };
+    }
+
+    fn memory_buckets(limiter: &RateLimiter) -> &Mutex<HashMap<String, Bucket>> {
+        let RateLimitStore::Memory(buckets) = &limiter.store else {
+            unreachable!("memory_limiter uses the memory store");
+        };
+        return buckets;
+    }
+
+    #[test]
+    fn memory_store_keeps_a_bucket_per_key() {
+        let limiter = memory_limiter();
+        let buckets = memory_buckets(&limiter);
+        let now = start();
+        for _ in 0..3 {
+            assert!(
+                limiter
+                    .take_from_memory(buckets, "ip:a", ONE_PER_SECOND, now)
+                    .allowed
+            );
+        }
+        assert!(
+            !limiter
+                .take_from_memory(buckets, "ip:a", ONE_PER_SECOND, now)
+                .allowed
+        );
+        assert!(
+            limiter
+                .take_from_memory(buckets, "ip:b", ONE_PER_SECOND, now)
+                .allowed
+        );
+    }
+
+    #[test]
+    fn memory_store_evicts_idle_buckets_when_full() {
+        let limiter = memory_limiter();
+        let buckets = memory_buckets(&limiter);
+        let now = start();
+        {
+            let mut buckets = buckets.lock().unwrap();
+            // Full again after 10 seconds with the user policy, the slower one
+            for index in 0..MAX_MEMORY_BUCKETS - 1 {
+                let bucket = Bucket {
+                    tokens: 0.0,
+                    refilled_at: now - Duration::seconds(10),
+                };
+                buckets.insert(format!("ip:{}", index), bucket);
+            }
+            let active = Bucket {
+                tokens: 0.0,
+                refilled_at: now - Duration::seconds(9),
+            };
+            buckets.insert(String::from("ip:active"), active);
+        }
+        limiter.take_from_memory(buckets, "ip:new", ONE_PER_SECOND, now);
+
+        let buckets = buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!(buckets.contains_key("ip:active"));
+        assert!(buckets.contains_key("ip:new"));
+    }
+
+    #[test]
+    fn parses_store_kind() {
+        assert_eq!(
+            RateLimitStoreKind::parse("memory"),
+            Some(RateLimitStoreKind::Memory)
+        );
+        assert_eq!(
+            RateLimitStoreKind::parse("postgres"),
+            Some(RateLimitStoreKind::Postgres)
+        );
+        assert_eq!(RateLimitStoreKind::parse("redis"), None);
+        assert_eq!(RateLimitStoreKind::parse("Memory"), None);
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use super::{environment::EnvironmentVariables, jwt::Claims};
+
+const MAX_CACHED_ENTRIES: usize = 10_000;
+
+struct CachedVerdict {
+    user_id: Uuid,
+    revoked: bool,
+    cached_until: Instant,
+}
+
+/// Postgres backed JWT denylist. Lookups are cached in memory so that only the
+/// first request carrying a given token, and one per cache period after that,
+/// reaches the database. Revocations made by this process apply immediately;
+/// revocations made by other instances apply once the cached verdict expires.
+pub struct TokenRevocation {
+    entries: RwLock<HashMap<Uuid, CachedVerdict>>,
+    ttl: Duration,
+}
+
+impl TokenRevocation {
+    pub fn initialize() -> TokenRevocation {
+        let environment_variables = EnvironmentVariables::initialize();
+        let ttl = Duration::from_secs(environment_variables.token_revocation_cache_seconds);
+        return TokenRevocation {
+            entries: RwLock::new(HashMap::new()),
+            ttl,
+        };
+    }
+
+    pub async fn is_revoked(
+        &self,
+        pool: &Pool<Postgres>,
+        jti: Uuid,
+        user_id: Uuid,
+        claims: &Claims,
+    ) -> Result<bool, sqlx::Error> {
+        if let Some(revoked) = self.cached(jti) {
+            return Ok(revoked);
+        }
+
+        let record = sqlx::query!(
+            r#"
+            SELECT
+                EXISTS(SELECT 1 FROM revoked_tokens WHERE jti = $1) AS "revoked!",
+                NOT EXISTS(SELECT 1 FROM users WHERE id = $2) AS "user_deleted!",
+                (SELECT revoked_before FROM user_token_revocations WHERE user_id = $2)
+                    AS revoked_before
+            "#,
+            jti,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        let revoked_before_issue = record
+            .revoked_before
+            .map(|revoked_before| issued_before(claims.iat, revoked_before))
+            .unwrap_or(false);
+        let revoked = record.revoked || record.user_deleted || revoked_before_issue;
+        self.remember(jti, user_id, revoked, claims.exp);
+        return Ok(revoked);
+    }
+
+    pub async fn revoke_token(
+        &self,
+        pool: &Pool<Postgres>,
+        jti: Uuid,
+        user_id: Uuid,
+        exp: usize,
+    ) -> Result<(), sqlx::Error> {
+        let expires_at = Utc
+            .timestamp_opt(exp as i64, 0)
+            .single()
+            .unwrap_or_else(Utc::now);
+        sqlx::query!(
+            r#"
+            INSERT INTO revoked_tokens (jti, user_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+            jti,
+            user_id,
+            expires_at
+        )
+        .execute(pool)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM revoked_tokens
+            WHERE expires_at < NOW()
+            "#
+        )
+        .execute(pool)
+        .await?;
+        self.remember(jti, user_id, true, exp);
+        return Ok(());
+    }
+
+    /// Revokes every token issued to `user_id` up to now.
+    pub async fn revoke_user_tokens(
+        &self,
+        pool: &Pool<Postgres>,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_token_revocations (user_id, revoked_before)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET revoked_before = EXCLUDED.revoked_before
+            "#,
+            user_id,
+            // Same clock as the `iat` of the tokens it is compared with
+            Utc::now()
+        )
+        .execute(pool)
+        .await?;
+        self.forget_user(user_id);
+        return Ok(());
+    }
+
+    /// Drops the cached verdicts of `user_id`'s tokens, so that the next
+    /// request checks them against the database again.
+    pub fn forget_user(&self, user_id: Uuid) {
+        let mut entries = self.entries.write().unwrap();
+        entries.retain(|_, verdict| verdict.user_id != user_id);
+    }
+
+    fn cached(&self, jti: Uuid) -> Option<bool> {
+        let entries = self.entries.read().unwrap();
+        let verdict = entries.get(&jti)?;
+        if verdict.cached_until <= Instant::now() {
+            return None;
+        }
+        return Some(verdict.revoked);
+    }
+
+    fn remember(&self, jti: Uuid, user_id: Uuid, revoked: bool, exp: usize) {
+        let now = Instant::now();
+        let cached_until = if revoked {
+            let remaining = (exp as i64 - Utc::now().timestamp()).max(0) as u64;
+            now + Duration::from_secs(remaining)
+        } else {
+            now + self.ttl
+        };
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() >= MAX_CACHED_ENTRIES {
+            entries.retain(|_, verdict| verdict.cached_until > now);
+        }
+        entries.insert(
+            jti,
+            CachedVerdict {
+                user_id,
+                revoked,
+                cached_until,
+            },
+        );
+    }
+}
+
+/// Whether a token issued at `iat` predates a revocation made at
+/// `revoked_before`. Both are compared to the microsecond.
+fn issued_before(iat: f64, revoked_before: DateTime<Utc>) -> bool {
+    return iat < revoked_before.timestamp_micros() as f64 / 1_000_000.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::issued_before;
+
+    fn iat(instant: chrono::DateTime<Utc>) -> f64 {
+        return instant.timestamp_micros() as f64 / 1_000_000.0;
+    }
+
+    #[test]
+    fn revokes_token_issued_earlier_in_the_same_second() {
+        let issued_at = Utc.timestamp_opt(1_700_000_000, 100_000_000).unwrap();
+        let revoked_before = issued_at + Duration::milliseconds(500);
+        assert!(issued_before(iat(issued_at), revoked_before));
+    }
+
+    #[test]
+    fn keeps_token_issued_later_in_the_same_second() {
+        let revoked_before = Utc.timestamp_opt(1_700_000_000, 100_000_000).unwrap();
+        let issued_at = revoked_before + Duration::milliseconds(500);
+        assert!(!issued_before(iat(issued_at), revoked_before));
+    }
+
+    #[test]
+    fn keeps_token_issued_at_the_revocation_instant() {
+        let revoked_before = Utc.timestamp_opt(1_700_000_000, 123_456_000).unwrap();
+        assert!(!issued_before(iat(revoked_before), revoked_before));
+    }
+
+    #[test]
+    fn revokes_tokens_from_earlier_seconds() {
+        let revoked_before = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert!(issued_before(1_699_999_999.0, revoked_before));
+    }
+
+    #[test]
+    fn revokes_whole_second_iat_from_the_same_second() {
+        // Tokens issued before `iat` had a fractional part
+        let revoked_before = Utc.timestamp_opt(1_700_000_000, 1_000).unwrap();
+        assert!(issued_before(1_700_000_000.0, revoked_before));
+    }
+}
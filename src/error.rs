@@ -0,0 +1,206 @@
+use std::fmt;
+
+use actix_web::{
+    error::{JsonPayloadError, PathError, QueryPayloadError},
+    http::StatusCode,
+    HttpResponse, ResponseError,
+};
+use jsonwebtoken::errors::ErrorKind;
+use serde::Serialize;
+use serde_json::json;
+use tracing::error;
+use utoipa::ToSchema;
+use validator::ValidationErrors;
+
+#[derive(Debug)]
+pub enum AppError {
+    Validation(ValidationErrors),
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    PreconditionFailed(String),
+    Locked(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    TooManyRequests(String),
+    /// The wrapped string is logged but never sent to the client.
+    Internal(String),
+}
+
+/// Body of every error response returned by the API.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub message: String,
+    #[serde(rename = "statusCode")]
+    pub status_code: u16,
+    /// Field level validation errors, only present on validation failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub errors: Option<serde_json::Value>,
+}
+
+impl AppError {
+    pub fn internal(error: impl fmt::Display) -> AppError {
+        return AppError::Internal(error.to_string());
+    }
+
+    /// Body sent to the client for this error.
+    pub fn to_error_response(&self) -> ErrorResponse {
+        let errors = match self {
+            AppError::Validation(errors) => Some(json!(errors)),
+            _ => None,
+        };
+        return ErrorResponse {
+            message: self.message(),
+            status_code: self.status_code().as_u16(),
+            errors,
+        };
+    }
+
+    fn message(&self) -> String {
+        return match self {
+            AppError::Validation(_) => String::from("validation failed"),
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::PreconditionFailed(message)
+            | AppError::Locked(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::UnsupportedMediaType(message)
+            | AppError::TooManyRequests(message) => message.clone(),
+            AppError::Internal(_) => String::from("internal server error"),
+        };
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            AppError::Validation(errors) => write!(formatter, "validation failed: {}", errors),
+            AppError::Internal(error) => write!(formatter, "internal server error: {}", error),
+            _ => write!(formatter, "{}", self.message()),
+        };
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        return match self {
+            AppError::Validation(_) | AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::Locked(_) => StatusCode::LOCKED,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let body = self.to_error_response();
+        match self {
+            AppError::Internal(error) => {
+                error!("{} {}", serde_json::to_string(&body).unwrap(), error)
+            }
+            _ => error!("{}", serde_json::to_string(&body).unwrap()),
+        }
+        return HttpResponse::build(self.status_code()).json(body);
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> AppError {
+        if let sqlx::Error::RowNotFound = error {
+            return AppError::NotFound(String::from("resource not found"));
+        }
+        if let Some(database_error) = error.as_database_error() {
+            if database_error.is_unique_violation() {
+                return AppError::Conflict(String::from("resource already exists"));
+            }
+        }
+        return AppError::internal(error);
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> AppError {
+        return AppError::Validation(errors);
+    }
+}
+
+impl From<JsonPayloadError> for AppError {
+    fn from(error: JsonPayloadError) -> AppError {
+        return match error {
+            JsonPayloadError::ContentType => AppError::UnsupportedMediaType(String::from(
+                "content type must be application/json",
+            )),
+            JsonPayloadError::Overflow { limit }
+            | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+                AppError::PayloadTooLarge(format!("payload must not exceed {} bytes", limit))
+            }
+            JsonPayloadError::Deserialize(error) => {
+                AppError::BadRequest(format!("invalid JSON payload: {}", error))
+            }
+            error => AppError::BadRequest(error.to_string()),
+        };
+    }
+}
+
+impl From<QueryPayloadError> for AppError {
+    fn from(error: QueryPayloadError) -> AppError {
+        return match error {
+            QueryPayloadError::Deserialize(error) => {
+                AppError::BadRequest(format!("invalid query string: {}", error))
+            }
+            error => AppError::BadRequest(error.to_string()),
+        };
+    }
+}
+
+impl From<PathError> for AppError {
+    fn from(error: PathError) -> AppError {
+        return match error {
+            PathError::Deserialize(error) => {
+                AppError::BadRequest(format!("invalid path parameter: {}", error))
+            }
+            error => AppError::BadRequest(error.to_string()),
+        };
+    }
+}
+
+impl From<argon2::password_hash::Error> for AppError {
+    fn from(error: argon2::password_hash::Error) -> AppError {
+        return AppError::internal(error);
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(error: jsonwebtoken::errors::Error) -> AppError {
+        return match error.kind() {
+            ErrorKind::ExpiredSignature => {
+                AppError::Unauthorized(String::from("JWT token has expired"))
+            }
+            ErrorKind::InvalidToken
+            | ErrorKind::InvalidSignature
+            | ErrorKind::InvalidAlgorithm
+            | ErrorKind::MissingAlgorithm
+            | ErrorKind::ImmatureSignature
+            | ErrorKind::InvalidIssuer
+            | ErrorKind::InvalidAudience
+            | ErrorKind::InvalidSubject
+            | ErrorKind::MissingRequiredClaim(_)
+            | ErrorKind::Base64(_)
+            | ErrorKind::Json(_)
+            | ErrorKind::Utf8(_) => AppError::Unauthorized(String::from("invalid JWT token")),
+            _ => AppError::internal(error),
+        };
+    }
+}
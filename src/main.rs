@@ -1,11 +1,23 @@
 #![allow(clippy::needless_return)]
 
+mod admin;
 mod config;
+mod error;
+mod projects;
+mod tags;
 mod todos;
 mod users;
 
 use actix_web::{get, http::StatusCode, web, App, HttpResponse, HttpServer, Responder};
-use config::{database::get_pool, environment::EnvironmentVariables, open_api::APIDocumentation};
+use config::{
+    database::get_pool,
+    environment::EnvironmentVariables,
+    extractors::{json_config, path_config, query_config},
+    mailer::initialize_mailer,
+    open_api::APIDocumentation,
+    rate_limit::RateLimiter,
+    token_revocation::TokenRevocation,
+};
 
 use serde_json::json;
 use tracing::info;
@@ -19,8 +31,14 @@ use crate::config::logger::initialize_logger;
 pub fn config(service_config: &mut web::ServiceConfig) {
     service_config.service(
         web::scope("/api")
+            .app_data(json_config())
+            .app_data(query_config())
+            .app_data(path_config())
             .configure(todos::routes::scoped_config)
-            .configure(users::routes::scoped_config),
+            .configure(tags::routes::scoped_config)
+            .configure(projects::routes::scoped_config)
+            .configure(users::routes::scoped_config)
+            .configure(admin::routes::scoped_config),
     );
 }
 
@@ -35,8 +53,10 @@ async fn health_check() -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let pool = get_pool().await;
-    let pool = web::Data::new(pool);
+    let pool = web::Data::new(get_pool().await);
+    let token_revocation = web::Data::new(TokenRevocation::initialize());
+    let rate_limiter = web::Data::new(RateLimiter::initialize());
+    let mailer = web::Data::from(initialize_mailer());
 
     let openapi = APIDocumentation::openapi();
 
@@ -47,6 +67,10 @@ async fn main() -> std::io::Result<()> {
 
     initialize_logger();
 
+    // Background jobs log their runs, so they start once the logger is set up.
+    todos::jobs::spawn_trash_purge(pool.get_ref().clone());
+    rate_limiter.spawn_bucket_purge(pool.get_ref().clone());
+
     let http_server = HttpServer::new(move || {
         App::new()
             .wrap(TracingLogger::default())
@@ -55,6 +79,9 @@ async fn main() -> std::io::Result<()> {
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", openapi.clone()),
             )
             .app_data(pool.clone())
+            .app_data(token_revocation.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(mailer.clone())
             .configure(config)
     })
     .bind(format!("{}:{}", web_server_host, web_server_port))?;
@@ -0,0 +1,109 @@
+#![allow(clippy::needless_return)]
+use super::dtos::{CreateProject, GetProjectsQueryParam, PathUuid, UpdateProject};
+use super::service;
+use crate::config::authenticated_user::AuthenticatedUser;
+use actix_web::{delete, get, patch, post, web, Responder};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    tag = "Projects",
+    path = "/api/projects",
+    params(GetProjectsQueryParam),
+    responses(
+        (status = 200, description = "Projects fetched successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+pub async fn get_projects(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    query: web::Query<GetProjectsQueryParam>,
+) -> impl Responder {
+    return service::get_projects(pool, authenticated_user, query).await;
+}
+
+#[utoipa::path(
+    tag = "Projects",
+    path = "/api/projects/{id}",
+    params(
+        ("id", description = "Unique storage id of Project")
+    ),
+    responses(
+        (status = 200, description = "Project fetched successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+pub async fn get_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::get_project(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Projects",
+    path = "/api/projects",
+    request_body = CreateProject,
+    responses(
+        (status = 201, description = "Project created successfully"),
+        (status = 400, description = "Invalid project name", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+pub async fn create_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    create_project_dto: web::Json<CreateProject>,
+) -> impl Responder {
+    return service::create_project(pool, authenticated_user, create_project_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Projects",
+    path = "/api/projects/{id}",
+    params(
+        ("id", description = "Unique storage id of Project")
+    ),
+    request_body = UpdateProject,
+    responses(
+        (status = 200, description = "Project updated successfully"),
+        (status = 400, description = "Invalid project name or the inbox was archived", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[patch("/{id}")]
+pub async fn update_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    update_project_dto: web::Json<UpdateProject>,
+) -> impl Responder {
+    return service::update_project(pool, authenticated_user, path, update_project_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Projects",
+    path = "/api/projects/{id}",
+    params(
+        ("id", description = "Unique storage id of Project")
+    ),
+    responses(
+        (status = 200, description = "Project deleted, its todos were moved to the inbox successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+pub async fn delete_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::delete_project(pool, authenticated_user, path).await;
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{types::Uuid, FromRow};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+pub const INBOX_NAME: &str = "Inbox";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathUuid {
+    pub id: Uuid,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+pub struct GetProjectsQueryParam {
+    /// Include archived projects in the listing
+    #[serde(rename = "includeArchived", default)]
+    pub include_archived: bool,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Project {
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(rename = "isInbox")]
+    pub is_inbox: bool,
+    #[serde(rename = "archivedAt")]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct ProjectWithCounts {
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(rename = "isInbox")]
+    pub is_inbox: bool,
+    #[serde(rename = "archivedAt")]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(rename = "todoCount")]
+    pub todo_count: i64,
+    #[serde(rename = "completedCount")]
+    pub completed_count: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateProject {
+    /// Surrounding whitespace is trimmed before the name is validated
+    #[serde(deserialize_with = "deserialize_trimmed")]
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "name must be between 1 and 100 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 100)]
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+pub struct UpdateProject {
+    /// Surrounding whitespace is trimmed before the name is validated
+    #[serde(default, deserialize_with = "deserialize_trimmed_option")]
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "name must be between 1 and 100 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 100)]
+    pub name: Option<String>,
+    /// Archive (`true`) or restore (`false`) the project; the inbox cannot be archived
+    pub archived: Option<bool>,
+}
+
+fn deserialize_trimmed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    return Ok(value.trim().to_owned());
+}
+
+fn deserialize_trimmed_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    return Ok(value.map(|value| value.trim().to_owned()));
+}
@@ -0,0 +1,4 @@
+pub mod controllers;
+pub mod dtos;
+pub mod routes;
+pub mod service;
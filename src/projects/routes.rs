@@ -0,0 +1,18 @@
+use crate::config::{jwt::JWTAuthentication, rate_limit::RateLimit};
+
+use super::controllers::{
+    create_project, delete_project, get_project, get_projects, update_project,
+};
+use actix_web::web;
+
+pub fn scoped_config(service_config: &mut web::ServiceConfig) {
+    let project_scope = web::scope("/projects")
+        .wrap(RateLimit::per_user())
+        .wrap(JWTAuthentication {})
+        .service(get_projects)
+        .service(get_project)
+        .service(create_project)
+        .service(update_project)
+        .service(delete_project);
+    service_config.service(project_scope);
+}
@@ -0,0 +1,306 @@
+use actix_web::{http::StatusCode, web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::{PgExecutor, Pool, Postgres};
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    config::authenticated_user::AuthenticatedUser,
+    error::AppError,
+    projects::dtos::{Project, ProjectWithCounts, INBOX_NAME},
+};
+
+use super::dtos::{CreateProject, GetProjectsQueryParam, PathUuid, UpdateProject};
+
+fn project_not_found(project_id: Uuid) -> AppError {
+    return AppError::NotFound(format!("project with ID: {} not found", project_id));
+}
+
+/// Locks the project row for the rest of the transaction, so a concurrent
+/// rename, archive or delete cannot slip in between checks and writes, and
+/// returns it.
+async fn lock_project<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    project_id: Uuid,
+) -> Result<Project, AppError> {
+    return sqlx::query_as!(
+        Project,
+        r#"
+        SELECT * FROM projects
+        WHERE id = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        project_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| project_not_found(project_id));
+}
+
+/// Returns the id of the user's inbox, creating it when it does not exist yet.
+pub async fn ensure_inbox<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<Uuid, sqlx::Error> {
+    return sqlx::query_scalar!(
+        r#"
+        WITH inserted AS (
+            INSERT INTO projects (user_id, name, is_inbox)
+            VALUES ($1, $2, TRUE)
+            ON CONFLICT (user_id) WHERE is_inbox DO NOTHING
+            RETURNING id
+        )
+        SELECT id AS "id!" FROM inserted
+        UNION ALL
+        SELECT id AS "id!" FROM projects WHERE user_id = $1 AND is_inbox
+        "#,
+        user_id,
+        INBOX_NAME
+    )
+    .fetch_one(executor)
+    .await;
+}
+
+/// Checks that `project_id` belongs to the user and is not archived, so todos
+/// can be placed in it.
+pub async fn active_project_id<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    project_id: Uuid,
+) -> Result<Uuid, AppError> {
+    let project = sqlx::query_as!(
+        Project,
+        r#"
+        SELECT * FROM projects
+        WHERE id = $1 AND user_id = $2
+        "#,
+        project_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| project_not_found(project_id))?;
+
+    if project.archived_at.is_some() {
+        return Err(AppError::BadRequest(format!(
+            "project with ID: {} is archived",
+            project_id
+        )));
+    }
+    return Ok(project.id);
+}
+
+async fn project_with_counts(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    project_id: Uuid,
+) -> Result<ProjectWithCounts, AppError> {
+    let project = sqlx::query_as!(
+        ProjectWithCounts,
+        r#"
+        SELECT
+            projects.*,
+            COUNT(todos.id) AS "todo_count!",
+            COUNT(todos.id) FILTER (WHERE todos.completed) AS "completed_count!"
+        FROM projects
+        LEFT JOIN todos ON todos.project_id = projects.id AND todos.deleted_at IS NULL
+        WHERE projects.id = $1 AND projects.user_id = $2
+        GROUP BY projects.id
+        "#,
+        project_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| project_not_found(project_id))?;
+    return Ok(project);
+}
+
+pub async fn get_projects(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    query: web::Query<GetProjectsQueryParam>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let projects = sqlx::query_as!(
+        ProjectWithCounts,
+        r#"
+        SELECT
+            projects.*,
+            COUNT(todos.id) AS "todo_count!",
+            COUNT(todos.id) FILTER (WHERE todos.completed) AS "completed_count!"
+        FROM projects
+        LEFT JOIN todos ON todos.project_id = projects.id AND todos.deleted_at IS NULL
+        WHERE projects.user_id = $1 AND ($2 OR projects.archived_at IS NULL)
+        GROUP BY projects.id
+        ORDER BY projects.is_inbox DESC, LOWER(projects.name)
+        "#,
+        user_id,
+        query.include_archived
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let json_projects = json!({
+        "data": projects,
+        "message": "projects fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_projects).unwrap());
+    return Ok(HttpResponse::Ok().json(json_projects));
+}
+
+pub async fn get_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let project_id = path.into_inner().id;
+    let project = project_with_counts(pool, user_id, project_id).await?;
+
+    let json_project = json!({
+        "data": project,
+        "message": "project fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_project).unwrap());
+    return Ok(HttpResponse::Ok().json(json_project));
+}
+
+pub async fn create_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    create_project_dto: web::Json<CreateProject>,
+) -> Result<HttpResponse, AppError> {
+    create_project_dto.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let project = sqlx::query_as!(
+        Project,
+        r#"
+        INSERT INTO projects (user_id, name)
+        VALUES ($1, $2)
+        RETURNING *
+        "#,
+        user_id,
+        create_project_dto.name
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let json_project = json!({
+        "data": project,
+        "message": "project created successfully",
+        "statusCode": StatusCode::CREATED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_project).unwrap());
+    return Ok(HttpResponse::Created().json(json_project));
+}
+
+pub async fn update_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    update_project_dto: web::Json<UpdateProject>,
+) -> Result<HttpResponse, AppError> {
+    update_project_dto.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let project_id = path.into_inner().id;
+    let mut transaction = pool.begin().await?;
+    let project = lock_project(&mut *transaction, user_id, project_id).await?;
+
+    let now = Utc::now();
+    let archived_at = match update_project_dto.archived {
+        Some(true) if project.is_inbox => {
+            return Err(AppError::BadRequest(String::from(
+                "inbox cannot be archived",
+            )));
+        }
+        Some(true) => project.archived_at.or(Some(now)),
+        Some(false) => None,
+        None => project.archived_at,
+    };
+    let name = update_project_dto
+        .name
+        .as_deref()
+        .unwrap_or(project.name.as_str());
+
+    sqlx::query!(
+        r#"
+        UPDATE projects
+        SET name = $1, archived_at = $2, updated_at = $3
+        WHERE id = $4
+        "#,
+        name,
+        archived_at,
+        now,
+        project_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    let project = project_with_counts(pool, user_id, project_id).await?;
+
+    let json_project = json!({
+        "data": project,
+        "message": "project updated successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_project).unwrap());
+    return Ok(HttpResponse::Ok().json(json_project));
+}
+
+pub async fn delete_project(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let project_id = path.into_inner().id;
+    let mut transaction = pool.begin().await?;
+    let project = lock_project(&mut *transaction, user_id, project_id).await?;
+    if project.is_inbox {
+        return Err(AppError::BadRequest(String::from(
+            "inbox cannot be deleted",
+        )));
+    }
+
+    let inbox_id = ensure_inbox(&mut *transaction, user_id).await?;
+    sqlx::query!(
+        r#"
+        UPDATE todos
+        SET project_id = $1, version = version + 1
+        WHERE project_id = $2
+        "#,
+        inbox_id,
+        project_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM projects
+        WHERE id = $1
+        "#,
+        project_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    let json_project = json!({
+        "message": "project deleted successfully, its todos were moved to the inbox",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_project).unwrap());
+    return Ok(HttpResponse::Ok().json(json_project));
+}
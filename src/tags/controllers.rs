@@ -0,0 +1,109 @@
+#![allow(clippy::needless_return)]
+use super::dtos::{CreateTag, PathUuid, UpdateTag};
+use super::service;
+use crate::config::authenticated_user::AuthenticatedUser;
+use actix_web::{delete, get, patch, post, web, Responder};
+use sqlx::{Pool, Postgres};
+
+#[utoipa::path(
+    tag = "Tags",
+    path = "/api/tags",
+    responses(
+        (status = 200, description = "Tags fetched successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+pub async fn get_tags(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> impl Responder {
+    return service::get_tags(pool, authenticated_user).await;
+}
+
+#[utoipa::path(
+    tag = "Tags",
+    path = "/api/tags/{id}",
+    params(
+        ("id", description = "Unique storage id of Tag")
+    ),
+    responses(
+        (status = 200, description = "Tag fetched successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}")]
+pub async fn get_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::get_tag(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Tags",
+    path = "/api/tags",
+    request_body = CreateTag,
+    responses(
+        (status = 201, description = "Tag created successfully"),
+        (status = 400, description = "Invalid tag name", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 409, description = "Tag already exist", body = ErrorResponse),
+    ),
+)]
+#[post("")]
+pub async fn create_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    create_tag_dto: web::Json<CreateTag>,
+) -> impl Responder {
+    return service::create_tag(pool, authenticated_user, create_tag_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Tags",
+    path = "/api/tags/{id}",
+    params(
+        ("id", description = "Unique storage id of Tag")
+    ),
+    request_body = UpdateTag,
+    responses(
+        (status = 200, description = "Tag updated successfully"),
+        (status = 400, description = "Invalid tag name", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse),
+        (status = 409, description = "Tag already exist", body = ErrorResponse),
+    ),
+)]
+#[patch("/{id}")]
+pub async fn update_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    update_tag_dto: web::Json<UpdateTag>,
+) -> impl Responder {
+    return service::update_tag(pool, authenticated_user, path, update_tag_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Tags",
+    path = "/api/tags/{id}",
+    params(
+        ("id", description = "Unique storage id of Tag")
+    ),
+    responses(
+        (status = 200, description = "Tag deleted successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Tag not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}")]
+pub async fn delete_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::delete_tag(pool, authenticated_user, path).await;
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{types::Uuid, FromRow};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+#[derive(Clone, Debug, Deserialize)]
+pub struct PathUuid {
+    pub id: Uuid,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct Tag {
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Tag as embedded in a todo.
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct TagSummary {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateTag {
+    /// Surrounding whitespace is trimmed before the name is validated
+    #[serde(deserialize_with = "deserialize_trimmed")]
+    #[validate(
+        length(
+            min = 1,
+            max = 50,
+            message = "name must be between 1 and 50 characters"
+        ),
+        custom = "validate_no_comma"
+    )]
+    #[schema(min_length = 1, max_length = 50)]
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+pub struct UpdateTag {
+    /// Surrounding whitespace is trimmed before the name is validated
+    #[serde(deserialize_with = "deserialize_trimmed")]
+    #[validate(
+        length(
+            min = 1,
+            max = 50,
+            message = "name must be between 1 and 50 characters"
+        ),
+        custom = "validate_no_comma"
+    )]
+    #[schema(min_length = 1, max_length = 50)]
+    pub name: String,
+}
+
+fn deserialize_trimmed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    return Ok(value.trim().to_owned());
+}
+
+/// The `tags` filter of `GET /api/todos` is comma separated, a name containing
+/// a comma could never be matched.
+fn validate_no_comma(value: &str) -> Result<(), ValidationError> {
+    if value.contains(',') {
+        let mut error = ValidationError::new("comma");
+        error.message = Some("name must not contain a comma".into());
+        return Err(error);
+    }
+    return Ok(());
+}
@@ -0,0 +1,16 @@
+use crate::config::{jwt::JWTAuthentication, rate_limit::RateLimit};
+
+use super::controllers::{create_tag, delete_tag, get_tag, get_tags, update_tag};
+use actix_web::web;
+
+pub fn scoped_config(service_config: &mut web::ServiceConfig) {
+    let tag_scope = web::scope("/tags")
+        .wrap(RateLimit::per_user())
+        .wrap(JWTAuthentication {})
+        .service(get_tags)
+        .service(get_tag)
+        .service(create_tag)
+        .service(update_tag)
+        .service(delete_tag);
+    service_config.service(tag_scope);
+}
@@ -0,0 +1,205 @@
+use actix_web::{http::StatusCode, web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::{PgExecutor, Pool, Postgres};
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{config::authenticated_user::AuthenticatedUser, error::AppError, tags::dtos::Tag};
+
+use super::dtos::{CreateTag, PathUuid, UpdateTag};
+
+/// Bumps the version of every todo carrying the tag, since todos embed their
+/// tags and their ETags have to change with them. `updated_at` is left alone,
+/// the todos themselves did not change.
+async fn touch_tagged_todos<'e, E: PgExecutor<'e>>(
+    executor: E,
+    tag_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE todos
+        SET version = version + 1
+        WHERE id IN (SELECT todo_id FROM todo_tags WHERE tag_id = $1)
+        "#,
+        tag_id
+    )
+    .execute(executor)
+    .await?;
+    return Ok(());
+}
+
+fn tag_not_found(tag_id: Uuid) -> AppError {
+    return AppError::NotFound(format!("tag with ID: {} not found", tag_id));
+}
+
+fn tag_conflict(error: sqlx::Error) -> AppError {
+    return match AppError::from(error) {
+        AppError::Conflict(_) => AppError::Conflict(String::from("tag already exist")),
+        error => error,
+    };
+}
+
+pub async fn get_tags(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let tags = sqlx::query_as!(
+        Tag,
+        r#"
+        SELECT * FROM tags
+        WHERE user_id = $1
+        ORDER BY LOWER(name)
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let json_tags = json!({
+        "data": tags,
+        "message": "tags fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_tags).unwrap());
+    return Ok(HttpResponse::Ok().json(json_tags));
+}
+
+pub async fn get_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let tag_id = path.into_inner().id;
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"
+        SELECT * FROM tags
+        WHERE id = $1 AND user_id = $2
+        "#,
+        tag_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| tag_not_found(tag_id))?;
+
+    let json_tag = json!({
+        "data": tag,
+        "message": "tag fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_tag).unwrap());
+    return Ok(HttpResponse::Ok().json(json_tag));
+}
+
+pub async fn create_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    create_tag_dto: web::Json<CreateTag>,
+) -> Result<HttpResponse, AppError> {
+    create_tag_dto.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"
+        INSERT INTO tags (user_id, name)
+        VALUES ($1, $2)
+        RETURNING *
+        "#,
+        user_id,
+        create_tag_dto.name
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(tag_conflict)?;
+
+    let json_tag = json!({
+        "data": tag,
+        "message": "tag created successfully",
+        "statusCode": StatusCode::CREATED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_tag).unwrap());
+    return Ok(HttpResponse::Created().json(json_tag));
+}
+
+pub async fn update_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    update_tag_dto: web::Json<UpdateTag>,
+) -> Result<HttpResponse, AppError> {
+    update_tag_dto.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let tag_id = path.into_inner().id;
+    let mut transaction = pool.begin().await?;
+    let tag = sqlx::query_as!(
+        Tag,
+        r#"
+        UPDATE tags
+        SET name = $1, updated_at = $2
+        WHERE id = $3 AND user_id = $4
+        RETURNING *
+        "#,
+        update_tag_dto.name,
+        Utc::now(),
+        tag_id,
+        user_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(tag_conflict)?
+    .ok_or_else(|| tag_not_found(tag_id))?;
+    touch_tagged_todos(&mut *transaction, tag_id).await?;
+    transaction.commit().await?;
+
+    let json_tag = json!({
+        "data": tag,
+        "message": "tag updated successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_tag).unwrap());
+    return Ok(HttpResponse::Ok().json(json_tag));
+}
+
+pub async fn delete_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let tag_id = path.into_inner().id;
+    let mut transaction = pool.begin().await?;
+    // Before the delete cascades to `todo_tags`, which finds the todos.
+    touch_tagged_todos(&mut *transaction, tag_id).await?;
+    let rows_affected = sqlx::query!(
+        r#"
+        DELETE FROM tags
+        WHERE id = $1 AND user_id = $2
+        "#,
+        tag_id,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+
+    if rows_affected == 0 {
+        return Err(tag_not_found(tag_id));
+    }
+    transaction.commit().await?;
+    let json_tag = json!({
+        "message": "tag deleted successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_tag).unwrap());
+    return Ok(HttpResponse::Ok().json(json_tag));
+}
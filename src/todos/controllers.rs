@@ -1,66 +1,177 @@
 #![allow(clippy::needless_return)]
-use super::dtos::{CreateTodo, GetTodosQueryParam, PathUuid, UpdateTodo};
+use super::dtos::{
+    BulkTodos, CreateTodo, CreateTodoItem, GetOccurrencesQueryParam, GetTodosQueryParam,
+    GetTrashQueryParam, PathUuid, ReplaceTodo, TodoItemPath, TodoTagPath, UpdateTodoItem,
+};
 use super::service;
-use actix_web::web::ReqData;
-use actix_web::{delete, get, patch, post, web, Responder};
+use crate::config::authenticated_user::AuthenticatedUser;
+use actix_web::{
+    delete, get, http::header::IfNoneMatch, patch, post, put, web, HttpRequest, Responder,
+};
 use sqlx::{Pool, Postgres};
 
-#[utoipa::path(tag = "Todos", path = "/api/todos", params(GetTodosQueryParam))]
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos",
+    params(GetTodosQueryParam),
+    responses(
+        (status = 200, description = "Todos fetched successfully"),
+        (status = 400, description = "Invalid pagination, filters or cursor", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
 #[get("")]
 pub async fn get_todos(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
     query: web::Query<GetTodosQueryParam>,
 ) -> impl Responder {
-    return service::get_todos(pool, req_data, query).await;
+    return service::get_todos(pool, authenticated_user.id, query).await;
 }
 
 #[utoipa::path(
     tag = "Todos",
     path = "/api/todos/{id}",
     params(
-        ("id", description = "Unique storage id of Todo")
+        ("id", description = "Unique storage id of Todo"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy, answered with 304 while it is current")
+    ),
+    responses(
+        (status = 200, description = "Todo fetched successfully"),
+        (status = 304, description = "Cached copy is current"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found", body = ErrorResponse),
     ),
 )]
 #[get("/{id}")]
 pub async fn get_todo(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
     path: web::Path<PathUuid>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
 ) -> impl Responder {
-    return service::get_todo(pool, req_data, path).await;
+    return service::get_todo(pool, authenticated_user, path, if_none_match).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/occurrences",
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        GetOccurrencesQueryParam
+    ),
+    responses(
+        (status = 200, description = "Upcoming occurrences fetched successfully"),
+        (status = 400, description = "Invalid limit", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/occurrences")]
+pub async fn get_todo_occurrences(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    query: web::Query<GetOccurrencesQueryParam>,
+) -> impl Responder {
+    return service::get_todo_occurrences(pool, authenticated_user, path, query).await;
 }
 
 #[utoipa::path(
     tag = "Todos",
     path = "/api/todos",
     request_body = CreateTodo,
+    responses(
+        (status = 201, description = "Todo created successfully"),
+        (status = 400, description = "Invalid title, description, reminder or recurrence, or project is archived", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Project not found", body = ErrorResponse),
+    ),
 )]
 #[post("")]
 pub async fn create_todo(
     pool: web::Data<Pool<Postgres>>,
     create_todo_dto: web::Json<CreateTodo>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
 ) -> impl Responder {
-    return service::create_todo(pool, req_data, create_todo_dto).await;
+    return service::create_todo(pool, authenticated_user, create_todo_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/bulk",
+    request_body = BulkTodos,
+    responses(
+        (status = 200, description = "Bulk operations processed, see each result for its outcome"),
+        (status = 400, description = "Empty or oversized batch, or an atomic batch failed validation", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "An atomic batch referenced a missing todo or project", body = ErrorResponse),
+    ),
+)]
+#[post("/bulk")]
+pub async fn bulk_todos(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    bulk_todos_dto: web::Json<BulkTodos>,
+) -> impl Responder {
+    return service::bulk_todos(pool, authenticated_user, bulk_todos_dto).await;
 }
 
 #[utoipa::path(
     tag = "Todos",
     path = "/api/todos/{id}",
     params(
-        ("id", description = "Unique storage id of Todo")
+        ("id", description = "Unique storage id of Todo"),
+        ("If-Match" = Option<String>, Header, description = "Only update the todo while it still has this ETag")
+    ),
+    request_body(
+        content = UpdateTodo,
+        description = "Fields to change. Also accepts an `application/merge-patch+json` (RFC 7396) patch of `ReplaceTodo`, where `null` clears a field and nested objects are merged"
+    ),
+    responses(
+        (status = 200, description = "Todo updated successfully"),
+        (status = 400, description = "Invalid payload, reminder or recurrence, or project is archived", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo or project not found", body = ErrorResponse),
+        (status = 412, description = "Todo was modified since the given ETag", body = ErrorResponse),
     ),
-    request_body = UpdateTodo,
 )]
 #[patch("/{id}")]
 pub async fn update_todo(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    patch_todo_dto: web::Json<serde_json::Value>,
+    request: HttpRequest,
+) -> impl Responder {
+    return service::update_todo(pool, authenticated_user, path, patch_todo_dto, request).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}",
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("If-Match" = Option<String>, Header, description = "Only replace the todo while it still has this ETag")
+    ),
+    request_body = ReplaceTodo,
+    responses(
+        (status = 200, description = "Todo replaced successfully"),
+        (status = 400, description = "Invalid payload, reminder or recurrence, or project is archived", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo or project not found", body = ErrorResponse),
+        (status = 412, description = "Todo was modified since the given ETag", body = ErrorResponse),
+    ),
+)]
+#[put("/{id}")]
+pub async fn replace_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
     path: web::Path<PathUuid>,
-    update_todo_dto: web::Json<UpdateTodo>,
+    replace_todo_dto: web::Json<ReplaceTodo>,
+    request: HttpRequest,
 ) -> impl Responder {
-    return service::update_todo(pool, req_data, path, update_todo_dto).await;
+    return service::replace_todo(pool, authenticated_user, path, replace_todo_dto, request).await;
 }
 
 #[utoipa::path(
@@ -68,13 +179,231 @@ pub async fn update_todo(
     path = "/api/todos/{id}",
     params(
         ("id", description = "Unique storage id of Todo")
-    )
+    ),
+    responses(
+        (status = 200, description = "Todo moved to trash successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found", body = ErrorResponse),
+    ),
 )]
 #[delete("/{id}")]
 pub async fn delete_todo(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::delete_todo(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/tags/{tag_id}",
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("tag_id", description = "Unique storage id of Tag")
+    ),
+    responses(
+        (status = 200, description = "Tag added to todo successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo or tag not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/tags/{tag_id}")]
+pub async fn add_todo_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoTagPath>,
+) -> impl Responder {
+    return service::add_todo_tag(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/tags/{tag_id}",
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("tag_id", description = "Unique storage id of Tag")
+    ),
+    responses(
+        (status = 200, description = "Tag removed from todo successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found or tag not attached", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/tags/{tag_id}")]
+pub async fn remove_todo_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoTagPath>,
+) -> impl Responder {
+    return service::remove_todo_tag(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/items",
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+    responses(
+        (status = 200, description = "Todo items fetched successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found", body = ErrorResponse),
+    ),
+)]
+#[get("/{id}/items")]
+pub async fn get_todo_items(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::get_todo_items(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/items",
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+    request_body = CreateTodoItem,
+    responses(
+        (status = 201, description = "Todo item created successfully"),
+        (status = 400, description = "Invalid todo item", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/items")]
+pub async fn create_todo_item(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    create_todo_item_dto: web::Json<CreateTodoItem>,
+) -> impl Responder {
+    return service::create_todo_item(pool, authenticated_user, path, create_todo_item_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/items/{item_id}",
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("item_id", description = "Unique storage id of Todo item")
+    ),
+    request_body = UpdateTodoItem,
+    responses(
+        (status = 200, description = "Todo item updated successfully"),
+        (status = 400, description = "Invalid todo item", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo or todo item not found", body = ErrorResponse),
+    ),
+)]
+#[patch("/{id}/items/{item_id}")]
+pub async fn update_todo_item(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoItemPath>,
+    update_todo_item_dto: web::Json<UpdateTodoItem>,
+) -> impl Responder {
+    return service::update_todo_item(pool, authenticated_user, path, update_todo_item_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/items/{item_id}",
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("item_id", description = "Unique storage id of Todo item")
+    ),
+    responses(
+        (status = 200, description = "Todo item deleted successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo or todo item not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/{id}/items/{item_id}")]
+pub async fn delete_todo_item(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoItemPath>,
+) -> impl Responder {
+    return service::delete_todo_item(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/trash",
+    params(GetTrashQueryParam),
+    responses(
+        (status = 200, description = "Trashed todos fetched successfully"),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[get("/trash")]
+pub async fn get_trash(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    query: web::Query<GetTrashQueryParam>,
+) -> impl Responder {
+    return service::get_trash(pool, authenticated_user, query).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/trash",
+    responses(
+        (status = 200, description = "Trash emptied successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[delete("/trash")]
+pub async fn empty_trash(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> impl Responder {
+    return service::empty_trash(pool, authenticated_user).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/trash/{id}",
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+    responses(
+        (status = 200, description = "Todo deleted permanently"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found in trash", body = ErrorResponse),
+    ),
+)]
+#[delete("/trash/{id}")]
+pub async fn purge_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::purge_todo(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Todos",
+    path = "/api/todos/{id}/restore",
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+    responses(
+        (status = 200, description = "Todo restored successfully, into the inbox when its project has been archived"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Todo not found in trash", body = ErrorResponse),
+    ),
+)]
+#[post("/{id}/restore")]
+pub async fn restore_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
     path: web::Path<PathUuid>,
 ) -> impl Responder {
-    return service::delete_todo(pool, req_data, path).await;
+    return service::restore_todo(pool, authenticated_user, path).await;
 }
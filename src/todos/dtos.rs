@@ -1,13 +1,30 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use actix_web::http::header::{EntityTag, IfMatch, IfNoneMatch};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use sqlx::{types::Uuid, FromRow};
 use utoipa::{IntoParams, ToSchema};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::{error::ErrorResponse, tags::dtos::TagSummary};
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct PathUuid {
     pub id: Uuid,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+pub struct TodoTagPath {
+    pub id: Uuid,
+    pub tag_id: Uuid,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TodoItemPath {
+    pub id: Uuid,
+    pub item_id: Uuid,
+}
+
 const fn limit_default() -> i64 {
     const LIMIT_DEFAULT: i64 = 10;
     return LIMIT_DEFAULT;
@@ -16,14 +33,187 @@ const fn offset_default() -> i64 {
     const OFFSET_DEFAULT: i64 = 0;
     return OFFSET_DEFAULT;
 }
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSort {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Title,
+}
+
+impl TodoSort {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            TodoSort::CreatedAt => "created_at",
+            TodoSort::UpdatedAt => "updated_at",
+            TodoSort::Title => "title",
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TagsMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        };
+    }
+}
+
+fn validate_date_ranges(query: &GetTodosQueryParam) -> Result<(), ValidationError> {
+    let ranges = [
+        (
+            query.created_from,
+            query.created_to,
+            "createdFrom must not be after createdTo",
+        ),
+        (
+            query.updated_from,
+            query.updated_to,
+            "updatedFrom must not be after updatedTo",
+        ),
+    ];
+    for (from, to, message) in ranges {
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                let mut error = ValidationError::new("date_range");
+                error.message = Some(message.into());
+                return Err(error);
+            }
+        }
+    }
+    return Ok(());
+}
+
+/// Rejects a `tags` filter without any names, `any` of none would match no
+/// todo and `all` of none every todo.
+fn validate_tag_list(tags: &str) -> Result<(), ValidationError> {
+    if tags.split(',').all(|tag_name| tag_name.trim().is_empty()) {
+        let mut error = ValidationError::new("tags");
+        error.message = Some("tags must contain at least one tag name".into());
+        return Err(error);
+    }
+    return Ok(());
+}
+
+/// Cursors only carry the creation time and id of the last todo, so they can
+/// not continue listings sorted by anything else.
+fn validate_cursor_sort(query: &GetTodosQueryParam) -> Result<(), ValidationError> {
+    if query.cursor.is_some() && !matches!(query.sort, TodoSort::CreatedAt) {
+        let mut error = ValidationError::new("cursor");
+        error.message = Some("cursor pagination only supports sort=created_at".into());
+        return Err(error);
+    }
+    return Ok(());
+}
+
 #[derive(Clone, Debug, Deserialize, IntoParams, Validate)]
+#[validate(schema(function = "validate_date_ranges", skip_on_field_errors = false))]
+#[validate(schema(function = "validate_cursor_sort"))]
 pub struct GetTodosQueryParam {
-    #[validate(range(min = 10))]
+    #[validate(range(min = 1, max = 100, message = "limit must be between 1 and 100"))]
     #[serde(default = "limit_default")]
     pub limit: i64,
-    #[validate(range(min = 0))]
+    #[validate(range(min = 0, message = "offset must not be negative"))]
     #[serde(default = "offset_default")]
     pub offset: i64,
+    /// Only return todos with this completion state
+    pub completed: Option<bool>,
+    /// Case-insensitive text matched against title and description
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "search must be between 1 and 200 characters"
+    ))]
+    pub search: Option<String>,
+    /// Only return todos created at or after this instant
+    #[serde(rename = "createdFrom")]
+    pub created_from: Option<DateTime<Utc>>,
+    /// Only return todos created at or before this instant
+    #[serde(rename = "createdTo")]
+    pub created_to: Option<DateTime<Utc>>,
+    /// Only return todos updated at or after this instant
+    #[serde(rename = "updatedFrom")]
+    pub updated_from: Option<DateTime<Utc>>,
+    /// Only return todos updated at or before this instant
+    #[serde(rename = "updatedTo")]
+    pub updated_to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: TodoSort,
+    #[serde(default)]
+    #[param(inline)]
+    pub order: SortOrder,
+    /// Only return todos whose due date has passed without being completed
+    pub overdue: Option<bool>,
+    /// Only return todos due on the current UTC day
+    #[serde(rename = "dueToday")]
+    pub due_today: Option<bool>,
+    /// Only return todos of this project
+    #[serde(rename = "projectId")]
+    pub project_id: Option<Uuid>,
+    /// Comma separated tag names, matched case-insensitively
+    #[validate(
+        length(
+            min = 1,
+            max = 500,
+            message = "tags must be between 1 and 500 characters"
+        ),
+        custom = "validate_tag_list"
+    )]
+    pub tags: Option<String>,
+    /// Whether a todo needs `any` or `all` of the given tags
+    #[serde(rename = "tagsMatch", default)]
+    #[param(inline)]
+    pub tags_match: TagsMatch,
+    /// Opaque `nextCursor` of a previous page; switches to keyset pagination and ignores `offset`
+    pub cursor: Option<String>,
+    /// Run the count query and include `total`/`totalPages`; defaults to true without a cursor
+    #[serde(rename = "includeTotal")]
+    pub include_total: Option<bool>,
+}
+
+impl GetTodosQueryParam {
+    /// `tags` split into distinct, lowercased names.
+    pub fn tag_names(&self) -> Option<Vec<String>> {
+        let tags = self.tags.as_ref()?;
+        let mut tag_names: Vec<String> = tags
+            .split(',')
+            .map(|tag_name| tag_name.trim().to_lowercase())
+            .filter(|tag_name| !tag_name.is_empty())
+            .collect();
+        tag_names.sort();
+        tag_names.dedup();
+        return Some(tag_names);
+    }
+
+    /// `search` as an ILIKE pattern with wildcard characters escaped.
+    pub fn search_pattern(&self) -> Option<String> {
+        return self.search.as_ref().map(|search| {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            return format!("%{}%", escaped);
+        });
+    }
 }
 
 #[derive(Clone, Debug, FromRow, Serialize)]
@@ -43,28 +233,605 @@ pub struct Todo {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    #[serde(rename = "dueAt")]
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: Option<String>,
+    #[serde(rename = "remindAt")]
+    pub remind_at: Option<DateTime<Utc>>,
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(skip)]
+    pub recurrence_frequency: Option<String>,
+    #[serde(skip)]
+    pub recurrence_interval: i32,
+    #[serde(skip)]
+    pub recurrence_weekdays: Vec<String>,
+    #[serde(skip)]
+    pub recurrence_until: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub recurrence_count: Option<i32>,
+    /// Position of this todo in its recurring series, starting at 1
+    pub occurrence: i32,
+    #[serde(rename = "previousOccurrenceId")]
+    pub previous_occurrence_id: Option<Uuid>,
+    /// Set while the todo is in the trash
+    #[serde(rename = "deletedAt")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Incremented on every change, sent as the `ETag` header
+    pub version: i32,
+}
+
+impl Todo {
+    pub fn etag(&self) -> EntityTag {
+        return EntityTag::new_strong(self.version.to_string());
+    }
+
+    /// Whether an `If-Match` precondition allows changing this todo.
+    pub fn matches(&self, if_match: &IfMatch) -> bool {
+        return match if_match {
+            IfMatch::Any => true,
+            IfMatch::Items(etags) => etags.iter().any(|etag| etag.strong_eq(&self.etag())),
+        };
+    }
+
+    /// Whether the copy a client holds according to `If-None-Match` is current.
+    pub fn is_cached_by(&self, if_none_match: &IfNoneMatch) -> bool {
+        return match if_none_match {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(etags) => etags.iter().any(|etag| etag.weak_eq(&self.etag())),
+        };
+    }
+}
+
+/// Checklist completion of a todo, `percentDone` is `null` when it has no items.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TodoProgress {
+    pub total: i64,
+    pub completed: i64,
+    #[serde(rename = "percentDone")]
+    pub percent_done: Option<i64>,
+}
+
+impl TodoProgress {
+    pub fn new(total: i64, completed: i64) -> TodoProgress {
+        let percent_done = (total > 0).then(|| completed * 100 / total);
+        return TodoProgress {
+            total,
+            completed,
+            percent_done,
+        };
+    }
+}
+
+/// Todo as returned by the API, with its tags and checklist progress embedded.
+#[derive(Clone, Debug, Serialize)]
+pub struct TodoResponse {
+    #[serde(flatten)]
+    pub todo: Todo,
+    pub tags: Vec<TagSummary>,
+    pub progress: TodoProgress,
+    pub recurrence: Option<Recurrence>,
+}
+
+/// Checklist item of a todo, ordered by `position` starting at 0.
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct TodoItem {
+    pub id: Uuid,
+    #[serde(rename = "todoId")]
+    pub todo_id: Uuid,
+    pub title: String,
+    pub completed: bool,
+    pub position: i32,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Position of the last todo of a page, handed to clients as an opaque string.
+#[derive(Clone, Debug)]
+pub struct TodoCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl TodoCursor {
+    pub fn encode(&self) -> String {
+        let cursor = format!("{}|{}", self.created_at.timestamp_micros(), self.id);
+        return URL_SAFE_NO_PAD.encode(cursor);
+    }
+
+    pub fn decode(cursor: &str) -> Option<TodoCursor> {
+        let cursor = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let cursor = String::from_utf8(cursor).ok()?;
+        let (created_at, id) = cursor.split_once('|')?;
+        let created_at = NaiveDateTime::from_timestamp_micros(created_at.parse().ok()?)?.and_utc();
+        let id = Uuid::parse_str(id).ok()?;
+        return Some(TodoCursor { created_at, id });
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
 pub struct GetTodosSuccess {
-    pub todos: Vec<Todo>,
-    pub total: i64,
-    pub page: i64,
+    pub todos: Vec<TodoResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<i64>,
     #[serde(rename = "perPage")]
     pub per_page: i64,
-    #[serde(rename = "totalPages")]
-    pub total_pages: i64,
+    #[serde(rename = "totalPages", skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<i64>,
+    /// Present while more todos follow when sorting by `created_at`
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, ToSchema)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoPriority {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl TodoPriority {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            TodoPriority::Low => "low",
+            TodoPriority::Medium => "medium",
+            TodoPriority::High => "high",
+            TodoPriority::Urgent => "urgent",
+        };
+    }
+
+    pub fn parse(value: &str) -> Option<TodoPriority> {
+        return match value {
+            "low" => Some(TodoPriority::Low),
+            "medium" => Some(TodoPriority::Medium),
+            "high" => Some(TodoPriority::High),
+            "urgent" => Some(TodoPriority::Urgent),
+            _ => None,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl RecurrenceFrequency {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            RecurrenceFrequency::Daily => "daily",
+            RecurrenceFrequency::Weekly => "weekly",
+            RecurrenceFrequency::Monthly => "monthly",
+            RecurrenceFrequency::Yearly => "yearly",
+        };
+    }
+
+    pub fn parse(value: &str) -> Option<RecurrenceFrequency> {
+        return match value {
+            "daily" => Some(RecurrenceFrequency::Daily),
+            "weekly" => Some(RecurrenceFrequency::Weekly),
+            "monthly" => Some(RecurrenceFrequency::Monthly),
+            "yearly" => Some(RecurrenceFrequency::Yearly),
+            _ => None,
+        };
+    }
+}
+
+/// Weekday in RRULE notation.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RecurrenceWeekday {
+    Mo,
+    Tu,
+    We,
+    Th,
+    Fr,
+    Sa,
+    Su,
+}
+
+impl RecurrenceWeekday {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            RecurrenceWeekday::Mo => "MO",
+            RecurrenceWeekday::Tu => "TU",
+            RecurrenceWeekday::We => "WE",
+            RecurrenceWeekday::Th => "TH",
+            RecurrenceWeekday::Fr => "FR",
+            RecurrenceWeekday::Sa => "SA",
+            RecurrenceWeekday::Su => "SU",
+        };
+    }
+
+    pub fn parse(value: &str) -> Option<RecurrenceWeekday> {
+        return match value {
+            "MO" => Some(RecurrenceWeekday::Mo),
+            "TU" => Some(RecurrenceWeekday::Tu),
+            "WE" => Some(RecurrenceWeekday::We),
+            "TH" => Some(RecurrenceWeekday::Th),
+            "FR" => Some(RecurrenceWeekday::Fr),
+            "SA" => Some(RecurrenceWeekday::Sa),
+            "SU" => Some(RecurrenceWeekday::Su),
+            _ => None,
+        };
+    }
+}
+
+const fn interval_default() -> i32 {
+    const INTERVAL_DEFAULT: i32 = 1;
+    return INTERVAL_DEFAULT;
+}
+
+fn validate_recurrence_weekdays(recurrence: &Recurrence) -> Result<(), ValidationError> {
+    if !recurrence.weekdays.is_empty() && recurrence.frequency != RecurrenceFrequency::Weekly {
+        let mut error = ValidationError::new("weekdays");
+        error.message = Some("weekdays can only be used with a weekly frequency".into());
+        return Err(error);
+    }
+    return Ok(());
+}
+
+/// Schedule of a recurring todo. Completing an occurrence creates the next one,
+/// due `interval` days, weeks, months or years later, or on the next listed
+/// weekday for weekly schedules. The series ends after `until` or once `count`
+/// occurrences exist.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_recurrence_weekdays"))]
+pub struct Recurrence {
+    pub frequency: RecurrenceFrequency,
+    #[serde(default = "interval_default")]
+    #[validate(range(min = 1, max = 365, message = "interval must be between 1 and 365"))]
+    pub interval: i32,
+    #[serde(default)]
+    pub weekdays: Vec<RecurrenceWeekday>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub until: Option<DateTime<Utc>>,
+    #[validate(range(min = 1, max = 1000, message = "count must be between 1 and 1000"))]
+    pub count: Option<i32>,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams, Validate)]
+pub struct GetTrashQueryParam {
+    #[validate(range(min = 1, max = 100, message = "limit must be between 1 and 100"))]
+    #[serde(default = "limit_default")]
+    pub limit: i64,
+    #[validate(range(min = 0, message = "offset must not be negative"))]
+    #[serde(default = "offset_default")]
+    pub offset: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams, Validate)]
+pub struct GetOccurrencesQueryParam {
+    /// Number of upcoming occurrences to return
+    #[validate(range(min = 1, max = 50, message = "limit must be between 1 and 50"))]
+    #[serde(default = "limit_default")]
+    pub limit: i64,
+}
+
+/// Distinguishes a field that is absent from the payload (`None`) from one
+/// explicitly set to `null` (`Some(None)`).
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    return Option::<T>::deserialize(deserializer).map(Some);
+}
+
+fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        let mut error = ValidationError::new("blank");
+        error.message = Some("must not be blank".into());
+        return Err(error);
+    }
+    return Ok(());
+}
+
+pub fn validate_reminder(
+    due_at: Option<DateTime<Utc>>,
+    remind_at: Option<DateTime<Utc>>,
+) -> Result<(), ValidationError> {
+    if let (Some(due_at), Some(remind_at)) = (due_at, remind_at) {
+        if remind_at > due_at {
+            let mut error = ValidationError::new("remind_at");
+            error.message = Some("remindAt must not be after dueAt".into());
+            return Err(error);
+        }
+    }
+    return Ok(());
+}
+
+pub fn validate_recurrence(
+    due_at: Option<DateTime<Utc>>,
+    recurrence: Option<&Recurrence>,
+) -> Result<(), ValidationError> {
+    if recurrence.is_some() && due_at.is_none() {
+        let mut error = ValidationError::new("recurrence");
+        error.message = Some("recurrence requires dueAt".into());
+        return Err(error);
+    }
+    return Ok(());
+}
+
+fn validate_create_todo(create_todo: &CreateTodo) -> Result<(), ValidationError> {
+    validate_reminder(create_todo.due_at, create_todo.remind_at)?;
+    return validate_recurrence(create_todo.due_at, create_todo.recurrence.as_ref());
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+#[validate(schema(function = "validate_create_todo"))]
 pub struct CreateTodo {
+    #[validate(length(max = 2000, message = "description must be at most 2000 characters"))]
+    #[schema(max_length = 2000)]
     pub description: String,
+    #[validate(
+        length(
+            min = 1,
+            max = 200,
+            message = "title must be between 1 and 200 characters"
+        ),
+        custom = "validate_not_blank"
+    )]
+    #[schema(min_length = 1, max_length = 200)]
     pub title: String,
+    #[serde(rename = "dueAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: Option<TodoPriority>,
+    #[serde(rename = "remindAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub remind_at: Option<DateTime<Utc>>,
+    /// Project to place the todo in, defaults to the inbox
+    #[serde(rename = "projectId")]
+    pub project_id: Option<Uuid>,
+    #[validate]
+    pub recurrence: Option<Recurrence>,
 }
 
-#[derive(Clone, Debug, Deserialize, ToSchema)]
+#[derive(Clone, Debug, Default, Deserialize, ToSchema, Validate)]
 pub struct UpdateTodo {
     pub completed: Option<bool>,
+    #[validate(length(max = 2000, message = "description must be at most 2000 characters"))]
+    #[schema(max_length = 2000)]
     pub description: Option<String>,
+    #[validate(
+        length(
+            min = 1,
+            max = 200,
+            message = "title must be between 1 and 200 characters"
+        ),
+        custom = "validate_not_blank"
+    )]
+    #[schema(min_length = 1, max_length = 200)]
     pub title: Option<String>,
+    /// Omit to keep the current value, `null` to clear it
+    #[serde(rename = "dueAt", default, deserialize_with = "deserialize_nullable")]
+    #[schema(value_type = Option<String>, format = DateTime, nullable)]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    /// Omit to keep the current value, `null` to clear it
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[schema(value_type = Option<TodoPriority>, nullable)]
+    pub priority: Option<Option<TodoPriority>>,
+    /// Omit to keep the current value, `null` to clear it
+    #[serde(
+        rename = "remindAt",
+        default,
+        deserialize_with = "deserialize_nullable"
+    )]
+    #[schema(value_type = Option<String>, format = DateTime, nullable)]
+    pub remind_at: Option<Option<DateTime<Utc>>>,
+    /// Move the todo to another project
+    #[serde(rename = "projectId")]
+    pub project_id: Option<Uuid>,
+    /// Omit to keep the current schedule, `null` to stop recurring
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[schema(value_type = Option<Recurrence>, nullable)]
+    pub recurrence: Option<Option<Recurrence>>,
+}
+
+/// Complete todo sent with `PUT`. Optional fields left out are cleared and the
+/// todo moves to the inbox unless a project is given.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ReplaceTodo {
+    #[schema(min_length = 1, max_length = 200)]
+    pub title: String,
+    #[schema(max_length = 2000)]
+    pub description: String,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(rename = "dueAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: Option<TodoPriority>,
+    #[serde(rename = "remindAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub remind_at: Option<DateTime<Utc>>,
+    #[serde(rename = "projectId")]
+    pub project_id: Option<Uuid>,
+    pub recurrence: Option<Recurrence>,
+}
+
+impl ReplaceTodo {
+    /// Current state of `todo`, the document merge patches are applied to.
+    pub fn from_todo(todo: &Todo) -> ReplaceTodo {
+        return ReplaceTodo {
+            title: todo.title.clone(),
+            description: todo.description.clone(),
+            completed: todo.completed,
+            due_at: todo.due_at,
+            priority: todo.priority.as_deref().and_then(TodoPriority::parse),
+            remind_at: todo.remind_at,
+            project_id: Some(todo.project_id),
+            recurrence: Recurrence::from_todo(todo),
+        };
+    }
+
+    /// Update that sets every field of the todo, placing it in `project_id`.
+    pub fn into_update(self, project_id: Uuid) -> UpdateTodo {
+        return UpdateTodo {
+            completed: Some(self.completed),
+            description: Some(self.description),
+            title: Some(self.title),
+            due_at: Some(self.due_at),
+            priority: Some(self.priority),
+            remind_at: Some(self.remind_at),
+            project_id: Some(project_id),
+            recurrence: Some(self.recurrence),
+        };
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+pub struct CreateTodoItem {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "title must be between 1 and 200 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 200)]
+    pub title: String,
+    /// Position to insert the item at, defaults to the end of the checklist
+    #[validate(range(min = 0, message = "position must not be negative"))]
+    pub position: Option<i32>,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema, Validate)]
+pub struct UpdateTodoItem {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "title must be between 1 and 200 characters"
+    ))]
+    #[schema(min_length = 1, max_length = 200)]
+    pub title: Option<String>,
+    pub completed: Option<bool>,
+    /// Move the item to this position, positions past the end move it last
+    #[validate(range(min = 0, message = "position must not be negative"))]
+    pub position: Option<i32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    /// Apply every operation or none of them
+    #[default]
+    Atomic,
+    /// Apply the operations that succeed and report the ones that fail
+    Partial,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkTodoOperation {
+    Create { data: CreateTodo },
+    Update { id: Uuid, data: UpdateTodo },
+    Complete { id: Uuid },
+    Delete { id: Uuid },
+}
+
+impl BulkTodoOperation {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            BulkTodoOperation::Create { .. } => "create",
+            BulkTodoOperation::Update { .. } => "update",
+            BulkTodoOperation::Complete { .. } => "complete",
+            BulkTodoOperation::Delete { .. } => "delete",
+        };
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct BulkTodos {
+    #[serde(default)]
+    pub mode: BulkMode,
+    pub operations: Vec<BulkTodoOperation>,
+}
+
+/// Outcome of one bulk operation, `index` is its position in the request.
+#[derive(Clone, Debug, Serialize)]
+pub struct BulkTodoResult {
+    pub index: usize,
+    pub op: &'static str,
+    #[serde(rename = "statusCode")]
+    pub status_code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<TodoResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::Query;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+    use validator::Validate;
+
+    use super::{validate_tag_list, GetTodosQueryParam, TodoCursor};
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = TodoCursor {
+            created_at: Utc.timestamp_opt(1_700_000_000, 123_456_000).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        let decoded = TodoCursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded.created_at, cursor.created_at);
+        assert_eq!(decoded.id, cursor.id);
+    }
+
+    #[test]
+    fn cursor_rejects_malformed_base64() {
+        assert!(TodoCursor::decode("not base64!").is_none());
+    }
+
+    #[test]
+    fn cursor_rejects_missing_separator() {
+        let cursor = URL_SAFE_NO_PAD.encode(format!("1700000000{}", Uuid::new_v4()));
+        assert!(TodoCursor::decode(&cursor).is_none());
+    }
+
+    #[test]
+    fn cursor_rejects_bad_uuid() {
+        let cursor = URL_SAFE_NO_PAD.encode("1700000000000000|not-a-uuid");
+        assert!(TodoCursor::decode(&cursor).is_none());
+    }
+
+    #[test]
+    fn cursor_rejects_bad_timestamp() {
+        let cursor = URL_SAFE_NO_PAD.encode(format!("yesterday|{}", Uuid::new_v4()));
+        assert!(TodoCursor::decode(&cursor).is_none());
+    }
+
+    #[test]
+    fn tag_list_needs_a_tag_name() {
+        assert!(validate_tag_list("work").is_ok());
+        assert!(validate_tag_list(" , work,").is_ok());
+        assert!(validate_tag_list(",,").is_err());
+        assert!(validate_tag_list(" , ").is_err());
+    }
+
+    #[test]
+    fn cursor_only_continues_created_at_listings() {
+        let query = |query_string| Query::<GetTodosQueryParam>::from_query(query_string).unwrap();
+        assert!(query("cursor=abc").validate().is_ok());
+        assert!(query("cursor=abc&sort=created_at&order=asc")
+            .validate()
+            .is_ok());
+        assert!(query("sort=title").validate().is_ok());
+        let errors = query("cursor=abc&sort=title").validate().unwrap_err();
+        assert!(errors.errors().contains_key("__all__"));
+        assert!(query("cursor=abc&sort=updated_at").validate().is_err());
+    }
 }
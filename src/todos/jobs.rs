@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use actix_web::rt;
+use chrono::Utc;
+use sqlx::{Pool, Postgres};
+use tracing::{error, info};
+
+use crate::config::environment::EnvironmentVariables;
+
+use super::service::purge_expired_trash;
+
+/// Empties the trash of todos older than `TRASH_RETENTION_DAYS`, once on
+/// startup and then every `TRASH_PURGE_INTERVAL_MINUTES`.
+pub fn spawn_trash_purge(pool: Pool<Postgres>) {
+    let environment_variables = EnvironmentVariables::initialize();
+    let retention = chrono::Duration::days(environment_variables.trash_retention_days);
+    let period =
+        Duration::from_secs(environment_variables.trash_purge_interval_minutes.max(1) * 60);
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(period);
+        loop {
+            interval.tick().await;
+            match purge_expired_trash(&pool, Utc::now() - retention).await {
+                Ok(0) => {}
+                Ok(purged) => info!("purged {} todos from the trash", purged),
+                Err(error) => error!("failed to purge the trash: {}", error),
+            }
+        }
+    });
+}
@@ -1,4 +1,6 @@
 pub mod controllers;
 pub mod dtos;
+pub mod jobs;
+pub mod recurrence;
 pub mod routes;
 pub mod service;
@@ -0,0 +1,250 @@
+use chrono::{DateTime, Datelike, Duration, Months, Utc};
+
+use super::dtos::{Recurrence, RecurrenceFrequency, RecurrenceWeekday, Todo};
+
+impl Recurrence {
+    /// Reads the schedule stored on `todo`, `None` when it does not recur.
+    pub fn from_todo(todo: &Todo) -> Option<Recurrence> {
+        let frequency = RecurrenceFrequency::parse(todo.recurrence_frequency.as_deref()?)?;
+        let weekdays = todo
+            .recurrence_weekdays
+            .iter()
+            .filter_map(|weekday| RecurrenceWeekday::parse(weekday))
+            .collect();
+        return Some(Recurrence {
+            frequency,
+            interval: todo.recurrence_interval,
+            weekdays,
+            until: todo.recurrence_until,
+            count: todo.recurrence_count,
+        });
+    }
+
+    pub fn weekday_names(&self) -> Vec<String> {
+        let mut weekdays = self.weekdays.clone();
+        weekdays.sort();
+        weekdays.dedup();
+        return weekdays
+            .iter()
+            .map(|weekday| weekday.as_str().to_owned())
+            .collect();
+    }
+
+    /// Due date of the occurrence following `occurrence`, which is due at
+    /// `due_at`. Returns `None` once the series has ended. As with RRULE,
+    /// monthly and yearly schedules skip months that lack the due day.
+    pub fn next_after(&self, due_at: DateTime<Utc>, occurrence: i32) -> Option<DateTime<Utc>> {
+        if self.count.is_some_and(|count| occurrence >= count) {
+            return None;
+        }
+        let interval = self.interval.max(1);
+        let next = match self.frequency {
+            RecurrenceFrequency::Daily => {
+                due_at.checked_add_signed(Duration::days(interval.into()))
+            }
+            RecurrenceFrequency::Weekly if self.weekdays.is_empty() => {
+                due_at.checked_add_signed(Duration::weeks(interval.into()))
+            }
+            RecurrenceFrequency::Weekly => self.next_weekday(due_at, interval),
+            RecurrenceFrequency::Monthly => Self::add_months(due_at, interval as u32),
+            RecurrenceFrequency::Yearly => Self::add_months(due_at, interval as u32 * 12),
+        }?;
+        if self.until.is_some_and(|until| next > until) {
+            return None;
+        }
+        return Some(next);
+    }
+
+    /// Up to `limit` due dates following `occurrence`, which is due at `due_at`.
+    pub fn upcoming(
+        &self,
+        due_at: DateTime<Utc>,
+        occurrence: i32,
+        limit: usize,
+    ) -> Vec<DateTime<Utc>> {
+        let mut occurrences = Vec::with_capacity(limit);
+        let mut current = (due_at, occurrence);
+        while occurrences.len() < limit {
+            let Some(next) = self.next_after(current.0, current.1) else {
+                break;
+            };
+            occurrences.push(next);
+            current = (next, current.1 + 1);
+        }
+        return occurrences;
+    }
+
+    /// First date `months` apart from `due_at`, or a multiple of it, that falls on
+    /// the same day of the month.
+    fn add_months(due_at: DateTime<Utc>, months: u32) -> Option<DateTime<Utc>> {
+        // Every day of the month recurs within 4 years, leap days included.
+        for step in 1..=48 {
+            let next = due_at.checked_add_months(Months::new(months.checked_mul(step)?))?;
+            if next.day() == due_at.day() {
+                return Some(next);
+            }
+        }
+        return None;
+    }
+
+    /// Next listed weekday later in the same week, or the first listed weekday
+    /// `interval` weeks on. Weeks start on Monday.
+    fn next_weekday(&self, due_at: DateTime<Utc>, interval: i32) -> Option<DateTime<Utc>> {
+        let mut weekdays: Vec<i64> = self
+            .weekdays
+            .iter()
+            .map(|weekday| *weekday as i64)
+            .collect();
+        weekdays.sort_unstable();
+        let current = i64::from(due_at.weekday().num_days_from_monday());
+        let days = match weekdays.iter().find(|weekday| **weekday > current) {
+            Some(weekday) => weekday - current,
+            None => 7 * i64::from(interval) - current + weekdays.first()?,
+        };
+        return due_at.checked_add_signed(Duration::days(days));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use crate::todos::dtos::{Recurrence, RecurrenceFrequency, RecurrenceWeekday};
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        return Utc.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap();
+    }
+
+    fn recurrence(frequency: RecurrenceFrequency) -> Recurrence {
+        return Recurrence {
+            frequency,
+            interval: 1,
+            weekdays: Vec::new(),
+            until: None,
+            count: None,
+        };
+    }
+
+    #[test]
+    fn daily_honours_interval() {
+        let mut daily = recurrence(RecurrenceFrequency::Daily);
+        daily.interval = 3;
+        assert_eq!(
+            daily.next_after(date(2023, 12, 30), 1),
+            Some(date(2024, 1, 2))
+        );
+    }
+
+    #[test]
+    fn monthly_skips_months_without_the_due_day() {
+        let monthly = recurrence(RecurrenceFrequency::Monthly);
+        assert_eq!(
+            monthly.next_after(date(2023, 1, 31), 1),
+            Some(date(2023, 3, 31))
+        );
+        assert_eq!(
+            monthly.upcoming(date(2023, 1, 31), 1, 4),
+            vec![
+                date(2023, 3, 31),
+                date(2023, 5, 31),
+                date(2023, 7, 31),
+                date(2023, 8, 31),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_keeps_the_30th_after_february() {
+        let monthly = recurrence(RecurrenceFrequency::Monthly);
+        assert_eq!(
+            monthly.next_after(date(2024, 1, 30), 1),
+            Some(date(2024, 3, 30))
+        );
+    }
+
+    #[test]
+    fn yearly_leap_day_waits_for_the_next_leap_year() {
+        let yearly = recurrence(RecurrenceFrequency::Yearly);
+        assert_eq!(
+            yearly.next_after(date(2024, 2, 29), 1),
+            Some(date(2028, 2, 29))
+        );
+    }
+
+    #[test]
+    fn yearly_regular_day() {
+        let yearly = recurrence(RecurrenceFrequency::Yearly);
+        assert_eq!(
+            yearly.next_after(date(2023, 2, 28), 1),
+            Some(date(2024, 2, 28))
+        );
+    }
+
+    #[test]
+    fn count_ends_the_series() {
+        let mut daily = recurrence(RecurrenceFrequency::Daily);
+        daily.count = Some(3);
+        assert_eq!(
+            daily.next_after(date(2023, 1, 1), 2),
+            Some(date(2023, 1, 2))
+        );
+        assert_eq!(daily.next_after(date(2023, 1, 2), 3), None);
+        assert_eq!(
+            daily.upcoming(date(2023, 1, 1), 1, 10),
+            vec![date(2023, 1, 2), date(2023, 1, 3)]
+        );
+    }
+
+    #[test]
+    fn until_ends_the_series() {
+        let mut weekly = recurrence(RecurrenceFrequency::Weekly);
+        weekly.until = Some(date(2023, 1, 15));
+        assert_eq!(
+            weekly.next_after(date(2023, 1, 1), 1),
+            Some(date(2023, 1, 8))
+        );
+        assert_eq!(
+            weekly.next_after(date(2023, 1, 8), 2),
+            Some(date(2023, 1, 15))
+        );
+        assert_eq!(weekly.next_after(date(2023, 1, 15), 3), None);
+    }
+
+    #[test]
+    fn weekdays_move_later_in_the_same_week() {
+        let mut weekly = recurrence(RecurrenceFrequency::Weekly);
+        weekly.weekdays = vec![RecurrenceWeekday::Fr, RecurrenceWeekday::Mo];
+        // 2023-01-02 is a Monday
+        assert_eq!(
+            weekly.next_after(date(2023, 1, 2), 1),
+            Some(date(2023, 1, 6))
+        );
+    }
+
+    #[test]
+    fn weekdays_wrap_to_the_next_week() {
+        let mut weekly = recurrence(RecurrenceFrequency::Weekly);
+        weekly.weekdays = vec![RecurrenceWeekday::Mo, RecurrenceWeekday::We];
+        // Friday 2023-01-06 and Sunday 2023-01-08 both wrap to Monday 2023-01-09
+        assert_eq!(
+            weekly.next_after(date(2023, 1, 6), 1),
+            Some(date(2023, 1, 9))
+        );
+        assert_eq!(
+            weekly.next_after(date(2023, 1, 8), 1),
+            Some(date(2023, 1, 9))
+        );
+    }
+
+    #[test]
+    fn weekdays_wrap_skips_interval_weeks() {
+        let mut weekly = recurrence(RecurrenceFrequency::Weekly);
+        weekly.interval = 2;
+        weekly.weekdays = vec![RecurrenceWeekday::Tu, RecurrenceWeekday::Th];
+        // Thursday 2023-01-05 wraps to Tuesday two weeks on
+        assert_eq!(
+            weekly.upcoming(date(2023, 1, 3), 1, 3),
+            vec![date(2023, 1, 5), date(2023, 1, 17), date(2023, 1, 19)]
+        );
+    }
+}
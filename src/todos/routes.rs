@@ -1,15 +1,33 @@
-use crate::config::jwt::JWTAuthentication;
+use crate::config::{jwt::JWTAuthentication, rate_limit::RateLimit};
 
-use super::controllers::{create_todo, delete_todo, get_todo, get_todos, update_todo};
+use super::controllers::{
+    add_todo_tag, bulk_todos, create_todo, create_todo_item, delete_todo, delete_todo_item,
+    empty_trash, get_todo, get_todo_items, get_todo_occurrences, get_todos, get_trash, purge_todo,
+    remove_todo_tag, replace_todo, restore_todo, update_todo, update_todo_item,
+};
 use actix_web::web;
 
 pub fn scoped_config(service_config: &mut web::ServiceConfig) {
     let todo_scope = web::scope("/todos")
+        .wrap(RateLimit::per_user())
         .wrap(JWTAuthentication {})
         .service(get_todos)
+        .service(get_trash)
+        .service(empty_trash)
+        .service(purge_todo)
         .service(get_todo)
+        .service(get_todo_occurrences)
         .service(create_todo)
+        .service(bulk_todos)
         .service(update_todo)
-        .service(delete_todo);
+        .service(replace_todo)
+        .service(delete_todo)
+        .service(add_todo_tag)
+        .service(remove_todo_tag)
+        .service(get_todo_items)
+        .service(create_todo_item)
+        .service(update_todo_item)
+        .service(delete_todo_item)
+        .service(restore_todo);
     service_config.service(todo_scope);
 }
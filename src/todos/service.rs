@@ -1,310 +1,1459 @@
+use std::collections::HashMap;
+
 use actix_web::{
-    http::StatusCode,
-    web::{self, ReqData},
-    HttpResponse, Responder,
+    http::{
+        header::{self, ETag, Header, IfMatch, IfNoneMatch},
+        StatusCode,
+    },
+    web, HttpMessage, HttpRequest, HttpResponse,
 };
-use chrono::Utc;
-use serde_json::json;
-use sqlx::{Pool, Postgres};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{Acquire, PgExecutor, Pool, Postgres, QueryBuilder, Transaction};
 use tracing::{error, info};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
-use crate::todos::dtos::{Count, Todo};
+use crate::{
+    config::{
+        authenticated_user::AuthenticatedUser, environment::EnvironmentVariables,
+        json_merge_patch::JsonMergePatch,
+    },
+    error::{AppError, ErrorResponse},
+    projects::service::{active_project_id, ensure_inbox},
+    tags::dtos::TagSummary,
+    todos::dtos::{Count, Todo, TodoItem, TodoProgress, TodoResponse},
+};
 
-use super::dtos::{CreateTodo, GetTodosQueryParam, GetTodosSuccess, PathUuid, UpdateTodo};
+use super::dtos::{
+    validate_recurrence, validate_reminder, BulkMode, BulkTodoOperation, BulkTodoResult, BulkTodos,
+    CreateTodo, CreateTodoItem, GetOccurrencesQueryParam, GetTodosQueryParam, GetTodosSuccess,
+    GetTrashQueryParam, PathUuid, Recurrence, ReplaceTodo, SortOrder, TagsMatch, TodoCursor,
+    TodoItemPath, TodoSort, TodoTagPath, UpdateTodo, UpdateTodoItem,
+};
 
-pub async fn get_todo(
-    pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
-    path: web::Path<PathUuid>,
-) -> impl Responder {
-    let user_id = Uuid::parse_str(req_data.unwrap().into_inner().as_str()).unwrap();
-    let pool = pool.as_ref();
-    let todo_id = path.into_inner().id;
-    let todo = sqlx::query_as!(
+/// `If-Match` precondition of the request, `None` when it has no such header.
+fn if_match(request: &HttpRequest) -> Option<IfMatch> {
+    if !request.headers().contains_key(header::IF_MATCH) {
+        return None;
+    }
+    // A header without a single valid ETag can never match.
+    return Some(IfMatch::parse(request).unwrap_or(IfMatch::Items(vec![])));
+}
+
+/// Whether the request body is a JSON Merge Patch rather than plain JSON.
+fn is_merge_patch(request: &HttpRequest) -> bool {
+    return matches!(
+        request.mime_type(),
+        Ok(Some(mime)) if mime.essence_str() == "application/merge-patch+json"
+    );
+}
+
+fn todo_not_found(todo_id: Uuid) -> AppError {
+    return AppError::NotFound(format!("todo with ID: {} not found", todo_id));
+}
+
+async fn with_details(
+    pool: &Pool<Postgres>,
+    todos: Vec<Todo>,
+) -> Result<Vec<TodoResponse>, AppError> {
+    let todo_ids: Vec<Uuid> = todos.iter().map(|todo| todo.id).collect();
+    let rows = sqlx::query!(
+        r#"
+        SELECT todo_tags.todo_id, tags.id, tags.name
+        FROM todo_tags
+        JOIN tags ON tags.id = todo_tags.tag_id
+        WHERE todo_tags.todo_id = ANY($1)
+        ORDER BY LOWER(tags.name)
+        "#,
+        &todo_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags_by_todo: HashMap<Uuid, Vec<TagSummary>> = HashMap::new();
+    for row in rows {
+        tags_by_todo
+            .entry(row.todo_id)
+            .or_default()
+            .push(TagSummary {
+                id: row.id,
+                name: row.name,
+            });
+    }
+    let progress_rows = sqlx::query!(
+        r#"
+        SELECT
+            todo_id,
+            COUNT(*) AS "total!",
+            COUNT(*) FILTER (WHERE completed) AS "completed!"
+        FROM todo_items
+        WHERE todo_id = ANY($1)
+        GROUP BY todo_id
+        "#,
+        &todo_ids
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut progress_by_todo: HashMap<Uuid, TodoProgress> = progress_rows
+        .into_iter()
+        .map(|row| (row.todo_id, TodoProgress::new(row.total, row.completed)))
+        .collect();
+
+    let todos = todos
+        .into_iter()
+        .map(|todo| {
+            let tags = tags_by_todo.remove(&todo.id).unwrap_or_default();
+            let progress = progress_by_todo.remove(&todo.id).unwrap_or_default();
+            let recurrence = Recurrence::from_todo(&todo);
+            return TodoResponse {
+                todo,
+                tags,
+                progress,
+                recurrence,
+            };
+        })
+        .collect();
+    return Ok(todos);
+}
+
+async fn find_todo<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Todo, AppError> {
+    return sqlx::query_as!(
         Todo,
         r#"
         SELECT * FROM todos
-        WHERE id = $1 AND user_id = $2
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
         "#,
         todo_id,
         user_id
     )
-    .fetch_one(pool)
-    .await;
-
-    match todo {
-        Ok(todo) => {
-            let json_todo = json!({
-                "data": todo,
-                "message": "todo fetched successfully",
-                "statusCode": StatusCode::OK.as_u16(),
-            });
-            info!("{}", serde_json::to_string(&json_todo).unwrap());
-            return HttpResponse::Ok().json(json_todo);
-        }
-        Err(error) => {
-            let json_error = json!({
-                "message": format!("todo with ID: {} not found", todo_id),
-                "statusCode": StatusCode::NOT_FOUND.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            error!("{}", error);
-            return HttpResponse::NotFound().json(json_error);
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| todo_not_found(todo_id));
+}
+
+/// Locks the todo row for the rest of the transaction, so concurrent changes to
+/// it or its checklist cannot interleave, and returns it.
+async fn lock_todo<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<Todo, AppError> {
+    return sqlx::query_as!(
+        Todo,
+        r#"
+        SELECT * FROM todos
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        FOR UPDATE
+        "#,
+        todo_id,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| todo_not_found(todo_id));
+}
+
+/// Bumps the todo's version after a change to its tags or checklist, which are
+/// part of its representation, and returns the new version.
+async fn touch_todo<'e, E: PgExecutor<'e>>(executor: E, todo_id: Uuid) -> Result<i32, AppError> {
+    let version = sqlx::query_scalar!(
+        r#"
+        UPDATE todos
+        SET version = version + 1
+        WHERE id = $1
+        RETURNING version
+        "#,
+        todo_id
+    )
+    .fetch_one(executor)
+    .await?;
+    return Ok(version);
+}
+
+async fn with_detail(pool: &Pool<Postgres>, todo: Todo) -> Result<TodoResponse, AppError> {
+    let mut todos = with_details(pool, vec![todo]).await?;
+    return Ok(todos.remove(0));
+}
+
+pub async fn get_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    if_none_match: Option<web::Header<IfNoneMatch>>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+    let todo = find_todo(pool, user_id, todo_id).await?;
+    let etag = ETag(todo.etag());
+    if let Some(if_none_match) = if_none_match {
+        if todo.is_cached_by(&if_none_match) {
+            return Ok(HttpResponse::NotModified().insert_header(etag).finish());
         }
     }
+    let todo = with_detail(pool, todo).await?;
+
+    let json_todo = json!({
+        "data": todo,
+        "message": "todo fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Ok().insert_header(etag).json(json_todo));
+}
+
+/// Appends the `WHERE` clause of a todo listing, shared by the page and its
+/// count so that both always filter the same todos.
+fn push_todo_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    user_id: Uuid,
+    query: &GetTodosQueryParam,
+) {
+    builder
+        .push(" WHERE user_id = ")
+        .push_bind(user_id)
+        .push(" AND deleted_at IS NULL");
+    if let Some(completed) = query.completed {
+        builder.push(" AND completed = ").push_bind(completed);
+    }
+    if let Some(search_pattern) = query.search_pattern() {
+        builder
+            .push(" AND (title ILIKE ")
+            .push_bind(search_pattern.clone())
+            .push(" OR description ILIKE ")
+            .push_bind(search_pattern)
+            .push(")");
+    }
+    if let Some(created_from) = query.created_from {
+        builder.push(" AND created_at >= ").push_bind(created_from);
+    }
+    if let Some(created_to) = query.created_to {
+        builder.push(" AND created_at <= ").push_bind(created_to);
+    }
+    if let Some(updated_from) = query.updated_from {
+        builder.push(" AND updated_at >= ").push_bind(updated_from);
+    }
+    if let Some(updated_to) = query.updated_to {
+        builder.push(" AND updated_at <= ").push_bind(updated_to);
+    }
+    if let Some(overdue) = query.overdue {
+        builder
+            .push(" AND (due_at IS NOT NULL AND due_at < NOW() AND NOT completed) = ")
+            .push_bind(overdue);
+    }
+    if let Some(due_today) = query.due_today {
+        builder
+            .push(
+                " AND (due_at IS NOT NULL \
+                AND (due_at AT TIME ZONE 'UTC')::DATE = (NOW() AT TIME ZONE 'UTC')::DATE) = ",
+            )
+            .push_bind(due_today);
+    }
+    if let Some(tag_names) = query.tag_names() {
+        let required_matches = match query.tags_match {
+            TagsMatch::Any => 1,
+            TagsMatch::All => tag_names.len() as i64,
+        };
+        builder
+            .push(
+                " AND (SELECT COUNT(DISTINCT LOWER(tags.name)) \
+                FROM todo_tags \
+                JOIN tags ON tags.id = todo_tags.tag_id \
+                WHERE todo_tags.todo_id = todos.id AND LOWER(tags.name) = ANY(",
+            )
+            .push_bind(tag_names)
+            .push(")) >= ")
+            .push_bind(required_matches);
+    }
+    if let Some(project_id) = query.project_id {
+        builder.push(" AND project_id = ").push_bind(project_id);
+    }
 }
 
+/// Lists the todos of `user_id`, who is the caller except for support staff
+/// looking at someone else's todos through the admin scope.
 pub async fn get_todos(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    user_id: Uuid,
     query: web::Query<GetTodosQueryParam>,
-) -> impl Responder {
-    let mut limit: i64 = 10;
-    let mut offset: i64 = 0;
-    let query_is_ok = query.validate().is_ok();
-    if query_is_ok {
-        limit = query.limit;
-        offset = query.offset;
-    }
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+    let cursor = match query.cursor.as_deref() {
+        Some(cursor) => {
+            let Some(cursor) = TodoCursor::decode(cursor) else {
+                return Err(AppError::BadRequest(String::from("invalid cursor")));
+            };
+            Some(cursor)
+        }
+        None => None,
+    };
+    let limit = query.limit;
+    let offset = if cursor.is_some() { 0 } else { query.offset };
+    let include_total = query.include_total.unwrap_or(cursor.is_none());
 
-    let user_id = Uuid::parse_str(req_data.unwrap().into_inner().as_str()).unwrap();
     let pool = pool.as_ref();
 
-    let count = sqlx::query_as!(
-        Count,
+    let mut total = None;
+    if include_total {
+        let mut count_query = QueryBuilder::new("SELECT COUNT(*) AS count FROM todos");
+        push_todo_filters(&mut count_query, user_id, &query);
+        let count = count_query
+            .build_query_as::<Count>()
+            .fetch_one(pool)
+            .await?;
+        total = Some(count.count.unwrap_or(0));
+    }
+
+    let mut todos_query = QueryBuilder::new("SELECT * FROM todos");
+    push_todo_filters(&mut todos_query, user_id, &query);
+    if let Some(cursor) = &cursor {
+        let comparison = match query.order {
+            SortOrder::Asc => ">",
+            SortOrder::Desc => "<",
+        };
+        todos_query
+            .push(format!(" AND (created_at, id) {} (", comparison))
+            .push_bind(cursor.created_at)
+            .push(", ")
+            .push_bind(cursor.id)
+            .push(")");
+    }
+    // Sort columns and directions come from enums, never from the request text.
+    let order = query.order.as_str();
+    todos_query.push(" ORDER BY ");
+    if !matches!(query.sort, TodoSort::CreatedAt) {
+        todos_query.push(format!("{} {}, ", query.sort.as_str(), order));
+    }
+    todos_query
+        .push(format!("created_at {}, id {}", order, order))
+        .push(" LIMIT ")
+        .push_bind(limit + 1)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let mut todos = todos_query.build_query_as::<Todo>().fetch_all(pool).await?;
+
+    let has_more = todos.len() as i64 > limit;
+    todos.truncate(limit as usize);
+    let todos = with_details(pool, todos).await?;
+    let next_cursor = match (has_more, query.sort, todos.last()) {
+        (true, TodoSort::CreatedAt, Some(last)) => Some(
+            TodoCursor {
+                created_at: last.todo.created_at,
+                id: last.todo.id,
+            }
+            .encode(),
+        ),
+        _ => None,
+    };
+
+    let data = GetTodosSuccess {
+        page: cursor.is_none().then_some((offset / limit) + 1),
+        per_page: limit,
+        todos,
+        total_pages: total.map(|total| (total + limit - 1) / limit),
+        total,
+        next_cursor,
+    };
+    let json_todos = json!({
+        "data": data,
+        "message": "todos fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todos).unwrap());
+    return Ok(HttpResponse::Ok().json(json_todos));
+}
+
+/// Validates and inserts a todo for `user_id`, placing it in the inbox unless
+/// another project is given.
+async fn insert_todo(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    create_todo_dto: &CreateTodo,
+) -> Result<Todo, AppError> {
+    create_todo_dto.validate()?;
+    let project_id = match create_todo_dto.project_id {
+        Some(project_id) => active_project_id(&mut **transaction, user_id, project_id).await?,
+        None => ensure_inbox(&mut **transaction, user_id).await?,
+    };
+
+    let recurrence = create_todo_dto.recurrence.as_ref();
+    let todo = sqlx::query_as!(
+        Todo,
         r#"
-        SELECT COUNT(*) AS count
-        FROM todos
-        WHERE user_id = $1
+        INSERT INTO todos (
+            title, description, user_id, due_at, priority, remind_at, project_id,
+            recurrence_frequency, recurrence_interval, recurrence_weekdays, recurrence_until,
+            recurrence_count
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING *;
         "#,
+        create_todo_dto.title,
+        create_todo_dto.description,
         user_id,
+        create_todo_dto.due_at,
+        create_todo_dto.priority.map(|priority| priority.as_str()),
+        create_todo_dto.remind_at,
+        project_id,
+        recurrence.map(|recurrence| recurrence.frequency.as_str()),
+        recurrence.map_or(1, |recurrence| recurrence.interval),
+        &recurrence
+            .map(Recurrence::weekday_names)
+            .unwrap_or_default(),
+        recurrence.and_then(|recurrence| recurrence.until),
+        recurrence.and_then(|recurrence| recurrence.count)
     )
-    .fetch_one(pool)
-    .await;
-
-    let todos_count: i64 = count.unwrap_or(Count { count: None }).count.unwrap_or(0);
+    .fetch_one(&mut **transaction)
+    .await?;
+    return Ok(todo);
+}
 
-    if todos_count <= 0 {
-        let json_error = json!({
-            "message": "todos does not exist",
-            "statusCode": StatusCode::NOT_FOUND.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        return HttpResponse::NotFound().json(json_error);
+/// Applies `update_todo_dto` to the user's todo. The row is locked while the
+/// merged values are validated, and the merge itself happens in the `UPDATE`,
+/// so fields absent from the payload keep whatever value is stored. When
+/// `if_match` is given the todo must still carry one of its ETags. Completing a
+/// recurring todo also creates its next occurrence.
+async fn apply_todo_update(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    todo_id: Uuid,
+    update_todo_dto: &UpdateTodo,
+    if_match: Option<&IfMatch>,
+) -> Result<Todo, AppError> {
+    update_todo_dto.validate()?;
+    let todo = lock_todo(&mut **transaction, user_id, todo_id).await?;
+    if let Some(if_match) = if_match {
+        if !todo.matches(if_match) {
+            return Err(AppError::PreconditionFailed(format!(
+                "todo with ID: {} has been modified",
+                todo_id
+            )));
+        }
     }
 
-    if offset >= todos_count {
-        let json_error = json!({
-            "message": "exceeds the total page",
-            "statusCode": StatusCode::BAD_REQUEST.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        return HttpResponse::BadRequest().json(json_error);
+    let due_at = update_todo_dto.due_at.unwrap_or(todo.due_at);
+    let remind_at = update_todo_dto.remind_at.unwrap_or(todo.remind_at);
+    let recurrence = match &update_todo_dto.recurrence {
+        Some(recurrence) => recurrence.clone(),
+        None => Recurrence::from_todo(&todo),
+    };
+    if let Some(recurrence) = &recurrence {
+        recurrence.validate()?;
+    }
+    let mut errors = ValidationErrors::new();
+    if let Err(error) = validate_reminder(due_at, remind_at) {
+        errors.add("__all__", error);
+    }
+    if let Err(error) = validate_recurrence(due_at, recurrence.as_ref()) {
+        errors.add("__all__", error);
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
     }
+    let project_id = match update_todo_dto.project_id {
+        Some(project_id) if project_id != todo.project_id => {
+            Some(active_project_id(&mut **transaction, user_id, project_id).await?)
+        }
+        _ => None,
+    };
+    let just_completed = update_todo_dto.completed == Some(true) && !todo.completed;
+    let priority = update_todo_dto
+        .priority
+        .map(|priority| priority.map(|priority| priority.as_str()));
+    let recurrence = update_todo_dto.recurrence.as_ref().map(Option::as_ref);
 
-    let todos = sqlx::query_as!(
+    let todo = sqlx::query_as!(
         Todo,
         r#"
-        SELECT * FROM todos
-        WHERE user_id = $1
-        ORDER BY created_at DESC
-        LIMIT $2
-        OFFSET $3
+        UPDATE todos
+        SET title = COALESCE($3, title),
+            description = COALESCE($4, description),
+            completed = COALESCE($5, completed),
+            due_at = CASE WHEN $6 THEN $7 ELSE due_at END,
+            priority = CASE WHEN $8 THEN $9 ELSE priority END,
+            remind_at = CASE WHEN $10 THEN $11 ELSE remind_at END,
+            project_id = COALESCE($12, project_id),
+            recurrence_frequency = CASE WHEN $13 THEN $14 ELSE recurrence_frequency END,
+            recurrence_interval = CASE WHEN $13 THEN $15 ELSE recurrence_interval END,
+            recurrence_weekdays = CASE WHEN $13 THEN $16 ELSE recurrence_weekdays END,
+            recurrence_until = CASE WHEN $13 THEN $17 ELSE recurrence_until END,
+            recurrence_count = CASE WHEN $13 THEN $18 ELSE recurrence_count END,
+            version = version + 1,
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2
+        RETURNING *
         "#,
+        todo_id,
         user_id,
-        limit,
-        offset
+        update_todo_dto.title,
+        update_todo_dto.description,
+        update_todo_dto.completed,
+        update_todo_dto.due_at.is_some(),
+        update_todo_dto.due_at.flatten(),
+        priority.is_some(),
+        priority.flatten(),
+        update_todo_dto.remind_at.is_some(),
+        update_todo_dto.remind_at.flatten(),
+        project_id,
+        recurrence.is_some(),
+        recurrence
+            .flatten()
+            .map(|recurrence| recurrence.frequency.as_str()),
+        recurrence
+            .flatten()
+            .map_or(1, |recurrence| recurrence.interval),
+        &recurrence
+            .flatten()
+            .map(Recurrence::weekday_names)
+            .unwrap_or_default(),
+        recurrence.flatten().and_then(|recurrence| recurrence.until),
+        recurrence.flatten().and_then(|recurrence| recurrence.count)
     )
-    .fetch_all(pool)
-    .await;
-
-    match todos {
-        Ok(todos) => {
-            let data = GetTodosSuccess {
-                page: (offset / limit) + 1,
-                per_page: limit,
-                todos,
-                total_pages: (todos_count / limit) + 1,
-                total: todos_count,
-            };
-            let json_todos = json!({
-                "data": data,
-                "message": "todos fetched successfully",
-                "statusCode": StatusCode::OK.as_u16(),
-            });
-            info!("{}", serde_json::to_string(&json_todos).unwrap());
-            return HttpResponse::Ok().json(json_todos);
-        }
-        Err(error) => {
-            let json_error = json!({
-                "message": "internal server error",
-                "statusCode": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            error!("{}", error);
-            return HttpResponse::InternalServerError().json(json_error);
-        }
+    .fetch_one(&mut **transaction)
+    .await?;
+    if just_completed {
+        spawn_next_occurrence(transaction, &todo).await?;
+    }
+    return Ok(todo);
+}
+
+/// Moves the todo to the trash, from where it can be restored until it is purged.
+async fn remove_todo<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    todo_id: Uuid,
+) -> Result<(), AppError> {
+    let rows_affected = sqlx::query!(
+        r#"
+        UPDATE todos
+        SET deleted_at = NOW(), version = version + 1
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        "#,
+        todo_id,
+        user_id
+    )
+    .execute(executor)
+    .await?
+    .rows_affected();
+
+    if rows_affected == 0 {
+        return Err(todo_not_found(todo_id));
     }
+    return Ok(());
 }
 
 pub async fn create_todo(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
     create_todo_dto: web::Json<CreateTodo>,
-) -> impl Responder {
-    let user_id = Uuid::parse_str(req_data.unwrap().into_inner().as_str()).unwrap();
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
     let pool = pool.as_ref();
+    let mut transaction = pool.begin().await?;
+    let todo = insert_todo(&mut transaction, user_id, &create_todo_dto).await?;
+    transaction.commit().await?;
 
-    let todo = sqlx::query_as!(
-        Todo,
+    let etag = ETag(todo.etag());
+    let recurrence = Recurrence::from_todo(&todo);
+    let todo = TodoResponse {
+        todo,
+        tags: vec![],
+        progress: TodoProgress::default(),
+        recurrence,
+    };
+
+    let json_todo = json!({
+        "data": todo,
+        "message": "todo created successfully",
+        "statusCode": StatusCode::CREATED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Created().insert_header(etag).json(json_todo));
+}
+
+/// Replaces every field of the user's todo with `replace_todo_dto`.
+async fn apply_todo_replace(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    todo_id: Uuid,
+    replace_todo_dto: ReplaceTodo,
+    if_match: Option<&IfMatch>,
+) -> Result<Todo, AppError> {
+    let project_id = match replace_todo_dto.project_id {
+        Some(project_id) => project_id,
+        None => ensure_inbox(&mut **transaction, user_id).await?,
+    };
+    let update_todo_dto = replace_todo_dto.into_update(project_id);
+    return apply_todo_update(transaction, user_id, todo_id, &update_todo_dto, if_match).await;
+}
+
+/// Applies an RFC 7396 merge patch to the user's todo. The patch is merged
+/// into the current todo and the result must be a valid `ReplaceTodo`.
+async fn apply_todo_merge_patch(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    todo_id: Uuid,
+    patch: Value,
+    if_match: Option<&IfMatch>,
+) -> Result<Todo, AppError> {
+    let todo = lock_todo(&mut **transaction, user_id, todo_id).await?;
+    let mut document =
+        serde_json::to_value(ReplaceTodo::from_todo(&todo)).map_err(AppError::internal)?;
+    JsonMergePatch::apply(&mut document, patch);
+    let replace_todo_dto = serde_json::from_value(document).map_err(invalid_payload)?;
+    return apply_todo_replace(transaction, user_id, todo_id, replace_todo_dto, if_match).await;
+}
+
+fn invalid_payload(error: serde_json::Error) -> AppError {
+    return AppError::BadRequest(format!("invalid payload: {}", error));
+}
+
+/// Sends the updated todo along with its new `ETag`.
+async fn updated_todo_response(
+    pool: &Pool<Postgres>,
+    todo: Todo,
+) -> Result<HttpResponse, AppError> {
+    let etag = ETag(todo.etag());
+    let todo = with_detail(pool, todo).await?;
+
+    let json_todo = json!({
+        "data": todo,
+        "message": "todo updated successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Ok().insert_header(etag).json(json_todo));
+}
+
+/// Partially updates a todo. Plain JSON bodies are read as `UpdateTodo`, while
+/// `application/merge-patch+json` bodies follow RFC 7396.
+pub async fn update_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    patch_todo_dto: web::Json<Value>,
+    request: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+    let if_match = if_match(&request);
+    let patch = patch_todo_dto.into_inner();
+    let mut transaction = pool.begin().await?;
+    let todo = if is_merge_patch(&request) {
+        apply_todo_merge_patch(&mut transaction, user_id, todo_id, patch, if_match.as_ref()).await?
+    } else {
+        let update_todo_dto: UpdateTodo = serde_json::from_value(patch).map_err(invalid_payload)?;
+        apply_todo_update(
+            &mut transaction,
+            user_id,
+            todo_id,
+            &update_todo_dto,
+            if_match.as_ref(),
+        )
+        .await?
+    };
+    transaction.commit().await?;
+    return updated_todo_response(pool, todo).await;
+}
+
+pub async fn replace_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    replace_todo_dto: web::Json<ReplaceTodo>,
+    request: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+    let mut transaction = pool.begin().await?;
+    let todo = apply_todo_replace(
+        &mut transaction,
+        user_id,
+        todo_id,
+        replace_todo_dto.into_inner(),
+        if_match(&request).as_ref(),
+    )
+    .await?;
+    transaction.commit().await?;
+    return updated_todo_response(pool, todo).await;
+}
+
+/// Creates the occurrence following `todo` when it recurs and its series has
+/// not ended. Tags and checklist items carry over, with every item unchecked.
+async fn spawn_next_occurrence(
+    transaction: &mut Transaction<'_, Postgres>,
+    todo: &Todo,
+) -> Result<(), AppError> {
+    let (Some(recurrence), Some(due_at)) = (Recurrence::from_todo(todo), todo.due_at) else {
+        return Ok(());
+    };
+    let Some(next_due_at) = recurrence.next_after(due_at, todo.occurrence) else {
+        return Ok(());
+    };
+    let next_remind_at = todo
+        .remind_at
+        .map(|remind_at| next_due_at - (due_at - remind_at));
+
+    // A todo that is reopened and completed again already has its successor.
+    let next_todo_id = sqlx::query_scalar!(
         r#"
-        INSERT INTO todos (title, description, user_id)
-        VALUES ($1, $2, $3)
-        RETURNING *;
+        INSERT INTO todos (
+            title, description, user_id, project_id, due_at, priority, remind_at,
+            recurrence_frequency, recurrence_interval, recurrence_weekdays, recurrence_until,
+            recurrence_count, occurrence, previous_occurrence_id
+        )
+        SELECT
+            title, description, user_id, project_id, $2, priority, $3,
+            recurrence_frequency, recurrence_interval, recurrence_weekdays, recurrence_until,
+            recurrence_count, occurrence + 1, id
+        FROM todos
+        WHERE id = $1
+        ON CONFLICT (previous_occurrence_id) DO NOTHING
+        RETURNING id
         "#,
-        create_todo_dto.title,
-        create_todo_dto.description,
-        user_id
+        todo.id,
+        next_due_at,
+        next_remind_at
     )
-    .fetch_one(pool)
-    .await;
-
-    match todo {
-        Ok(todo) => {
-            let json_todo = json!({
-                "data": todo,
-                "message": "todo created successfully",
-                "statusCode": StatusCode::CREATED.as_u16(),
-            });
-            info!("{}", serde_json::to_string(&json_todo).unwrap());
-            return HttpResponse::Created().json(json_todo);
-        }
-        Err(error) => {
-            let json_error = json!({
-                "message": "internal server error",
-                "statusCode": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            error!("{}", error);
-            return HttpResponse::InternalServerError().json(json_error);
+    .fetch_optional(&mut **transaction)
+    .await?;
+    let Some(next_todo_id) = next_todo_id else {
+        return Ok(());
+    };
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_tags (todo_id, tag_id)
+        SELECT $1, tag_id FROM todo_tags
+        WHERE todo_id = $2
+        "#,
+        next_todo_id,
+        todo.id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_items (todo_id, title, position)
+        SELECT $1, title, position FROM todo_items
+        WHERE todo_id = $2
+        "#,
+        next_todo_id,
+        todo.id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    info!("todo {} recurred as todo {}", todo.id, next_todo_id);
+    return Ok(());
+}
+
+pub async fn get_todo_occurrences(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    query: web::Query<GetOccurrencesQueryParam>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+    let todo = find_todo(pool, user_id, todo_id).await?;
+    let occurrences = match (Recurrence::from_todo(&todo), todo.due_at) {
+        (Some(recurrence), Some(due_at)) => {
+            recurrence.upcoming(due_at, todo.occurrence, query.limit as usize)
         }
-    }
+        _ => vec![],
+    };
+
+    let json_occurrences = json!({
+        "data": {
+            "occurrences": occurrences,
+        },
+        "message": "todo occurrences fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_occurrences).unwrap());
+    return Ok(HttpResponse::Ok().json(json_occurrences));
 }
 
-pub async fn update_todo(
+pub async fn delete_todo(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
     path: web::Path<PathUuid>,
-    update_todo_dto: web::Json<UpdateTodo>,
-) -> impl Responder {
-    let user_id = Uuid::parse_str(req_data.unwrap().into_inner().as_str()).unwrap();
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
     let pool = pool.as_ref();
     let todo_id = path.into_inner().id;
-    let query_result = sqlx::query_as!(
-        Todo,
+    remove_todo(pool, user_id, todo_id).await?;
+
+    let json_todo = json!({
+        "message": "todo moved to trash successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Ok().json(json_todo));
+}
+
+pub async fn add_todo_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoTagPath>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let TodoTagPath {
+        id: todo_id,
+        tag_id,
+    } = path.into_inner();
+    let mut todo = find_todo(pool, user_id, todo_id).await?;
+
+    let rows_affected = sqlx::query!(
         r#"
-        SELECT * FROM todos
-        WHERE id = $1 AND user_id = $2
+        INSERT INTO todo_tags (todo_id, tag_id)
+        SELECT $1, id FROM tags
+        WHERE id = $2 AND user_id = $3
+        ON CONFLICT DO NOTHING
         "#,
         todo_id,
+        tag_id,
         user_id
     )
-    .fetch_one(pool)
-    .await;
-
-    if let Err(error) = query_result {
-        let json_error = json!({
-            "message": format!("todo with ID: {} not found", todo_id),
-            "statusCode": StatusCode::NOT_FOUND.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        error!("{}", error);
-        return HttpResponse::NotFound().json(json_error);
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if rows_affected > 0 {
+        todo.version = touch_todo(pool, todo_id).await?;
+    } else {
+        let tag_exists = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM tags WHERE id = $1 AND user_id = $2) AS "exists!"
+            "#,
+            tag_id,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+        if !tag_exists {
+            return Err(AppError::NotFound(format!(
+                "tag with ID: {} not found",
+                tag_id
+            )));
+        }
     }
+    let todo = with_detail(pool, todo).await?;
 
-    let now = Utc::now();
-    let todo = query_result.unwrap();
+    let json_todo = json!({
+        "data": todo,
+        "message": "tag added to todo successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Ok().json(json_todo));
+}
 
-    let query_result = sqlx::query_as!(
-        Todo,
+pub async fn remove_todo_tag(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoTagPath>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let TodoTagPath {
+        id: todo_id,
+        tag_id,
+    } = path.into_inner();
+    let mut todo = find_todo(pool, user_id, todo_id).await?;
+
+    let rows_affected = sqlx::query!(
         r#"
-        UPDATE todos
-        SET title = $1, description = $2, completed = $3, updated_at = $4
+        DELETE FROM todo_tags
+        WHERE todo_id = $1 AND tag_id = $2
+        "#,
+        todo_id,
+        tag_id
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if rows_affected == 0 {
+        return Err(AppError::NotFound(format!(
+            "tag with ID: {} is not attached to todo with ID: {}",
+            tag_id, todo_id
+        )));
+    }
+    todo.version = touch_todo(pool, todo_id).await?;
+    let todo = with_detail(pool, todo).await?;
+
+    let json_todo = json!({
+        "data": todo,
+        "message": "tag removed from todo successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Ok().json(json_todo));
+}
+
+fn todo_item_not_found(item_id: Uuid) -> AppError {
+    return AppError::NotFound(format!("todo item with ID: {} not found", item_id));
+}
+
+pub async fn get_todo_items(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+    find_todo(pool, user_id, todo_id).await?;
+    let items = sqlx::query_as!(
+        TodoItem,
+        r#"
+        SELECT * FROM todo_items
+        WHERE todo_id = $1
+        ORDER BY position
+        "#,
+        todo_id
+    )
+    .fetch_all(pool)
+    .await?;
+    let completed = items.iter().filter(|item| item.completed).count() as i64;
+    let progress = TodoProgress::new(items.len() as i64, completed);
+
+    let json_items = json!({
+        "data": {
+            "items": items,
+            "progress": progress,
+        },
+        "message": "todo items fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_items).unwrap());
+    return Ok(HttpResponse::Ok().json(json_items));
+}
+
+pub async fn create_todo_item(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+    create_todo_item_dto: web::Json<CreateTodoItem>,
+) -> Result<HttpResponse, AppError> {
+    create_todo_item_dto.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+
+    let mut transaction = pool.begin().await?;
+    lock_todo(&mut *transaction, user_id, todo_id).await?;
+    let item_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*)::INTEGER AS "count!" FROM todo_items
+        WHERE todo_id = $1
+        "#,
+        todo_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    let position = create_todo_item_dto
+        .position
+        .map_or(item_count, |position| position.min(item_count));
+    sqlx::query!(
+        r#"
+        UPDATE todo_items
+        SET position = position + 1
+        WHERE todo_id = $1 AND position >= $2
+        "#,
+        todo_id,
+        position
+    )
+    .execute(&mut *transaction)
+    .await?;
+    let item = sqlx::query_as!(
+        TodoItem,
+        r#"
+        INSERT INTO todo_items (todo_id, title, position)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+        todo_id,
+        create_todo_item_dto.title,
+        position
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    touch_todo(&mut *transaction, todo_id).await?;
+    transaction.commit().await?;
+
+    let json_item = json!({
+        "data": item,
+        "message": "todo item created successfully",
+        "statusCode": StatusCode::CREATED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_item).unwrap());
+    return Ok(HttpResponse::Created().json(json_item));
+}
+
+pub async fn update_todo_item(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoItemPath>,
+    update_todo_item_dto: web::Json<UpdateTodoItem>,
+) -> Result<HttpResponse, AppError> {
+    update_todo_item_dto.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let TodoItemPath {
+        id: todo_id,
+        item_id,
+    } = path.into_inner();
+
+    let mut transaction = pool.begin().await?;
+    lock_todo(&mut *transaction, user_id, todo_id).await?;
+    let item = sqlx::query_as!(
+        TodoItem,
+        r#"
+        SELECT * FROM todo_items
+        WHERE id = $1 AND todo_id = $2
+        "#,
+        item_id,
+        todo_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| todo_item_not_found(item_id))?;
+
+    let mut position = item.position;
+    if let Some(requested_position) = update_todo_item_dto.position {
+        let last_position = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*)::INTEGER - 1 AS "last_position!" FROM todo_items
+            WHERE todo_id = $1
+            "#,
+            todo_id
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        position = requested_position.min(last_position);
+        // Items between the old and the new position move one step towards the gap.
+        let shift = (item.position - position).signum();
+        sqlx::query!(
+            r#"
+            UPDATE todo_items
+            SET position = position + $2
+            WHERE todo_id = $1 AND id <> $3 AND position BETWEEN $4 AND $5
+            "#,
+            todo_id,
+            shift,
+            item_id,
+            position.min(item.position),
+            position.max(item.position)
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    let title = update_todo_item_dto.title.as_ref().unwrap_or(&item.title);
+    let completed = update_todo_item_dto.completed.unwrap_or(item.completed);
+    let item = sqlx::query_as!(
+        TodoItem,
+        r#"
+        UPDATE todo_items
+        SET title = $1, completed = $2, position = $3, updated_at = $4
         WHERE id = $5
         RETURNING *
         "#,
-        update_todo_dto.title.to_owned().unwrap_or(todo.title),
-        update_todo_dto
-            .description
-            .to_owned()
-            .unwrap_or(todo.description),
-        update_todo_dto
-            .completed
-            .to_owned()
-            .unwrap_or(todo.completed),
-        now,
+        title,
+        completed,
+        position,
+        Utc::now(),
+        item_id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    touch_todo(&mut *transaction, todo_id).await?;
+    transaction.commit().await?;
+
+    let json_item = json!({
+        "data": item,
+        "message": "todo item updated successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_item).unwrap());
+    return Ok(HttpResponse::Ok().json(json_item));
+}
+
+pub async fn delete_todo_item(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<TodoItemPath>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let TodoItemPath {
+        id: todo_id,
+        item_id,
+    } = path.into_inner();
+
+    let mut transaction = pool.begin().await?;
+    lock_todo(&mut *transaction, user_id, todo_id).await?;
+    let position = sqlx::query_scalar!(
+        r#"
+        DELETE FROM todo_items
+        WHERE id = $1 AND todo_id = $2
+        RETURNING position
+        "#,
+        item_id,
         todo_id
     )
-    .fetch_one(pool)
-    .await;
-
-    match query_result {
-        Ok(todo) => {
-            let json_todo = json!({
-                "data": todo,
-                "message": "todo updated successfully",
-                "statusCode": StatusCode::OK.as_u16(),
-            });
-            info!("{}", serde_json::to_string(&json_todo).unwrap());
-            return HttpResponse::Ok().json(json_todo);
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| todo_item_not_found(item_id))?;
+    sqlx::query!(
+        r#"
+        UPDATE todo_items
+        SET position = position - 1
+        WHERE todo_id = $1 AND position > $2
+        "#,
+        todo_id,
+        position
+    )
+    .execute(&mut *transaction)
+    .await?;
+    touch_todo(&mut *transaction, todo_id).await?;
+    transaction.commit().await?;
+
+    let json_item = json!({
+        "message": "todo item deleted successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_item).unwrap());
+    return Ok(HttpResponse::Ok().json(json_item));
+}
+
+async fn run_bulk_operation(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    operation: &BulkTodoOperation,
+) -> Result<Option<Todo>, AppError> {
+    return match operation {
+        BulkTodoOperation::Create { data } => {
+            insert_todo(transaction, user_id, data).await.map(Some)
         }
-        Err(error) => {
-            let json_error = json!({
-                "message": "todo updating failed",
-                "statusCode": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            error!("{}", error);
-            return HttpResponse::InternalServerError().json(json_error);
+        BulkTodoOperation::Update { id, data } => {
+            apply_todo_update(transaction, user_id, *id, data, None)
+                .await
+                .map(Some)
+        }
+        BulkTodoOperation::Complete { id } => {
+            let complete = UpdateTodo {
+                completed: Some(true),
+                ..UpdateTodo::default()
+            };
+            apply_todo_update(transaction, user_id, *id, &complete, None)
+                .await
+                .map(Some)
+        }
+        BulkTodoOperation::Delete { id } => remove_todo(&mut **transaction, user_id, *id)
+            .await
+            .map(|_| None),
+    };
+}
+
+pub async fn bulk_todos(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    bulk_todos_dto: web::Json<BulkTodos>,
+) -> Result<HttpResponse, AppError> {
+    let environment_variables = EnvironmentVariables::initialize();
+    let max_operations = environment_variables.bulk_max_operations;
+    let BulkTodos { mode, operations } = bulk_todos_dto.into_inner();
+    if operations.is_empty() {
+        return Err(AppError::BadRequest(String::from(
+            "operations must not be empty",
+        )));
+    }
+    if operations.len() > max_operations {
+        return Err(AppError::BadRequest(format!(
+            "at most {} operations are allowed per request",
+            max_operations
+        )));
+    }
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+
+    let mut transaction = pool.begin().await?;
+    let mut outcomes = Vec::with_capacity(operations.len());
+    for (index, operation) in operations.iter().enumerate() {
+        let outcome = match mode {
+            BulkMode::Atomic => run_bulk_operation(&mut transaction, user_id, operation).await,
+            BulkMode::Partial => {
+                // A savepoint keeps a failed operation from aborting the others.
+                let mut savepoint = transaction.begin().await?;
+                let outcome = run_bulk_operation(&mut savepoint, user_id, operation).await;
+                match outcome {
+                    Ok(_) => savepoint.commit().await?,
+                    Err(_) => savepoint.rollback().await?,
+                }
+                outcome
+            }
+        };
+        if let (BulkMode::Atomic, Err(error)) = (mode, &outcome) {
+            transaction.rollback().await?;
+            let error = error.to_error_response();
+            let body = ErrorResponse {
+                message: format!(
+                    "operation {} ({}) failed, no changes were applied",
+                    index,
+                    operation.as_str()
+                ),
+                status_code: error.status_code,
+                errors: Some(json!([BulkTodoResult {
+                    index,
+                    op: operation.as_str(),
+                    status_code: error.status_code,
+                    data: None,
+                    error: Some(error.clone()),
+                }])),
+            };
+            error!("{}", serde_json::to_string(&body).unwrap());
+            let status_code = StatusCode::from_u16(error.status_code)
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            return Ok(HttpResponse::build(status_code).json(body));
         }
+        outcomes.push((index, operation.as_str(), outcome));
     }
+    transaction.commit().await?;
+
+    let todos = outcomes
+        .iter()
+        .filter_map(|(_, _, outcome)| outcome.as_ref().ok().cloned().flatten())
+        .collect();
+    let mut todos = with_details(pool, todos).await?.into_iter();
+    let mut failed = 0;
+    let results: Vec<BulkTodoResult> = outcomes
+        .into_iter()
+        .map(|(index, op, outcome)| match outcome {
+            Ok(todo) => BulkTodoResult {
+                index,
+                op,
+                status_code: match op {
+                    "create" => StatusCode::CREATED.as_u16(),
+                    _ => StatusCode::OK.as_u16(),
+                },
+                data: todo.and_then(|_| todos.next()),
+                error: None,
+            },
+            Err(error) => {
+                failed += 1;
+                let error = error.to_error_response();
+                BulkTodoResult {
+                    index,
+                    op,
+                    status_code: error.status_code,
+                    data: None,
+                    error: Some(error),
+                }
+            }
+        })
+        .collect();
+
+    let json_results = json!({
+        "data": {
+            "results": results,
+            "succeeded": results.len() - failed,
+            "failed": failed,
+        },
+        "message": "bulk operations processed successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_results).unwrap());
+    return Ok(HttpResponse::Ok().json(json_results));
 }
 
-pub async fn delete_todo(
+pub async fn get_trash(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    query: web::Query<GetTrashQueryParam>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM todos
+        WHERE user_id = $1 AND deleted_at IS NOT NULL
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    let todos = sqlx::query_as!(
+        Todo,
+        r#"
+        SELECT * FROM todos
+        WHERE user_id = $1 AND deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC, id DESC
+        LIMIT $2
+        OFFSET $3
+        "#,
+        user_id,
+        query.limit,
+        query.offset
+    )
+    .fetch_all(pool)
+    .await?;
+    let todos = with_details(pool, todos).await?;
+
+    let data = GetTodosSuccess {
+        page: Some((query.offset / query.limit) + 1),
+        per_page: query.limit,
+        todos,
+        total_pages: Some((total + query.limit - 1) / query.limit),
+        total: Some(total),
+        next_cursor: None,
+    };
+    let json_todos = json!({
+        "data": data,
+        "message": "trash fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todos).unwrap());
+    return Ok(HttpResponse::Ok().json(json_todos));
+}
+
+fn trashed_todo_not_found(todo_id: Uuid) -> AppError {
+    return AppError::NotFound(format!("todo with ID: {} not found in trash", todo_id));
+}
+
+pub async fn restore_todo(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let todo_id = path.into_inner().id;
+    let mut transaction = pool.begin().await?;
+    let inbox_id = ensure_inbox(&mut *transaction, user_id).await?;
+    // A project archived while the todo was in the trash takes no new todos,
+    // the todo comes back in the inbox instead.
+    let todo = sqlx::query_as!(
+        Todo,
+        r#"
+        UPDATE todos
+        SET deleted_at = NULL,
+            project_id = CASE
+                WHEN EXISTS (
+                    SELECT 1 FROM projects
+                    WHERE projects.id = todos.project_id AND projects.archived_at IS NOT NULL
+                )
+                THEN $3
+                ELSE project_id
+            END,
+            version = version + 1,
+            updated_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+        RETURNING *
+        "#,
+        todo_id,
+        user_id,
+        inbox_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| trashed_todo_not_found(todo_id))?;
+    transaction.commit().await?;
+    let todo = with_detail(pool, todo).await?;
+
+    let json_todo = json!({
+        "data": todo,
+        "message": "todo restored successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_todo).unwrap());
+    return Ok(HttpResponse::Ok().json(json_todo));
+}
+
+pub async fn purge_todo(
     pool: web::Data<Pool<Postgres>>,
-    req_data: Option<ReqData<String>>,
+    authenticated_user: AuthenticatedUser,
     path: web::Path<PathUuid>,
-) -> impl Responder {
-    let user_id = Uuid::parse_str(req_data.unwrap().into_inner().as_str()).unwrap();
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
     let pool = pool.as_ref();
     let todo_id = path.into_inner().id;
     let rows_affected = sqlx::query!(
         r#"
         DELETE FROM todos
-        WHERE id = $1 AND user_id = $2
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
         "#,
         todo_id,
         user_id
     )
     .execute(pool)
-    .await
-    .unwrap()
+    .await?
     .rows_affected();
 
     if rows_affected == 0 {
-        let json_error = json!({
-            "message": format!("todo with ID: {} not found", todo_id),
-            "statusCode": StatusCode::NOT_FOUND.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        return HttpResponse::NotFound().json(json_error);
+        return Err(trashed_todo_not_found(todo_id));
     }
     let json_todo = json!({
-        "message": "todo deleted successfully",
+        "message": "todo deleted permanently",
         "statusCode": StatusCode::OK.as_u16(),
     });
     info!("{}", serde_json::to_string(&json_todo).unwrap());
-    return HttpResponse::Ok().json(json_todo);
+    return Ok(HttpResponse::Ok().json(json_todo));
+}
+
+pub async fn empty_trash(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let rows_affected = sqlx::query!(
+        r#"
+        DELETE FROM todos
+        WHERE user_id = $1 AND deleted_at IS NOT NULL
+        "#,
+        user_id
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    let json_response = json!({
+        "data": {
+            "purged": rows_affected,
+        },
+        "message": "trash emptied successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+/// Permanently deletes todos of every user that were trashed before `cutoff`.
+pub async fn purge_expired_trash(
+    pool: &Pool<Postgres>,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    let query_result = sqlx::query!(
+        r#"
+        DELETE FROM todos
+        WHERE deleted_at < $1
+        "#,
+        cutoff
+    )
+    .execute(pool)
+    .await?;
+    return Ok(query_result.rows_affected());
 }
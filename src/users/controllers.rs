@@ -1,33 +1,355 @@
 #![allow(clippy::needless_return)]
 
-use actix_web::{post, web, Responder};
+use actix_web::{delete, get, patch, post, put, web, Responder};
 use sqlx::{Pool, Postgres};
 
-use super::dtos::{LoginUser, RegisterUser};
+use crate::config::{
+    authenticated_user::AuthenticatedUser, client_info::ClientInfo, jwt::JWTAuthentication,
+    mailer::Mailer, rate_limit::RateLimit, token_revocation::TokenRevocation,
+};
+
+use super::dtos::{
+    ChangeEmail, ChangePassword, CreatePersonalAccessToken, DeleteAccount, ForgotPassword,
+    GetLoginAttemptsQueryParam, LoginUser, PathUuid, RefreshToken, RegisterUser,
+    ResendVerificationEmail, ResetPassword, SignOutUser, VerifyEmail,
+};
 use super::service;
 
 #[utoipa::path(
     tag = "Users",
     path = "/api/users/sign-up",
     request_body = RegisterUser,
+    responses(
+        (status = 201, description = "User registered successfully"),
+        (status = 400, description = "Invalid email or password", body = ErrorResponse),
+        (status = 409, description = "Email already exist", body = ErrorResponse),
+    ),
 )]
-#[post("/sign-up")]
+#[post("/sign-up", wrap = "RateLimit::per_ip()")]
 pub async fn register_user(
     pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
     register_user_dto: web::Json<RegisterUser>,
 ) -> impl Responder {
-    return service::register_user(pool, register_user_dto).await;
+    return service::register_user(pool, mailer, register_user_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/verify-email",
+    request_body = VerifyEmail,
+    responses(
+        (status = 200, description = "Email verified successfully"),
+        (status = 400, description = "Invalid, expired or already used token", body = ErrorResponse),
+    ),
+)]
+#[post("/verify-email", wrap = "RateLimit::per_ip()")]
+pub async fn verify_email(
+    pool: web::Data<Pool<Postgres>>,
+    verify_email_dto: web::Json<VerifyEmail>,
+) -> impl Responder {
+    return service::verify_email(pool, verify_email_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/verify-email/resend",
+    request_body = ResendVerificationEmail,
+    responses(
+        (status = 202, description = "Verification email sent if the account exists and is not verified yet"),
+        (status = 400, description = "Invalid email", body = ErrorResponse),
+    ),
+)]
+#[post("/verify-email/resend", wrap = "RateLimit::per_ip()")]
+pub async fn resend_verification_email(
+    pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
+    resend_verification_email_dto: web::Json<ResendVerificationEmail>,
+) -> impl Responder {
+    return service::resend_verification_email(pool, mailer, resend_verification_email_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/forgot-password",
+    request_body = ForgotPassword,
+    responses(
+        (status = 202, description = "Password reset email sent if the account exists"),
+        (status = 400, description = "Invalid email", body = ErrorResponse),
+    ),
+)]
+#[post("/forgot-password", wrap = "RateLimit::per_ip()")]
+pub async fn forgot_password(
+    pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
+    forgot_password_dto: web::Json<ForgotPassword>,
+) -> impl Responder {
+    return service::forgot_password(pool, mailer, forgot_password_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/reset-password",
+    request_body = ResetPassword,
+    responses(
+        (status = 200, description = "Password reset successfully, all sessions are signed out"),
+        (status = 400, description = "Invalid password or invalid, expired or already used token", body = ErrorResponse),
+    ),
+)]
+#[post("/reset-password", wrap = "RateLimit::per_ip()")]
+pub async fn reset_password(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    reset_password_dto: web::Json<ResetPassword>,
+) -> impl Responder {
+    return service::reset_password(pool, token_revocation, reset_password_dto).await;
 }
 
 #[utoipa::path(
     tag = "Users",
     path = "/api/users/sign-in",
     request_body = LoginUser,
+    responses(
+        (status = 200, description = "User logged in successfully"),
+        (status = 400, description = "Invalid email or password", body = ErrorResponse),
+        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+        (status = 403, description = "Account disabled, or email address not verified while verification is required", body = ErrorResponse),
+        (status = 423, description = "Correct password but account locked after too many failed sign-in attempts", body = ErrorResponse),
+    ),
 )]
-#[post("/sign-in")]
+#[post("/sign-in", wrap = "RateLimit::per_ip()")]
 pub async fn login_user(
     pool: web::Data<Pool<Postgres>>,
     login_user_dto: web::Json<LoginUser>,
+    client_info: ClientInfo,
+) -> impl Responder {
+    return service::login_user(pool, login_user_dto, client_info).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/refresh",
+    request_body = RefreshToken,
+    responses(
+        (status = 200, description = "Token refreshed successfully"),
+        (status = 400, description = "Missing refresh token", body = ErrorResponse),
+        (status = 401, description = "Invalid, expired or reused refresh token", body = ErrorResponse),
+        (status = 403, description = "Account has been disabled", body = ErrorResponse),
+    ),
+)]
+#[post("/refresh", wrap = "RateLimit::per_ip()")]
+pub async fn refresh_token(
+    pool: web::Data<Pool<Postgres>>,
+    refresh_token_dto: web::Json<RefreshToken>,
+) -> impl Responder {
+    return service::refresh_token(pool, refresh_token_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/sign-out",
+    request_body(content = Option<SignOutUser>),
+    responses(
+        (status = 200, description = "User signed out successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[post(
+    "/sign-out",
+    wrap = "RateLimit::per_user()",
+    wrap = "JWTAuthentication"
+)]
+pub async fn sign_out(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    sign_out_dto: Option<web::Json<SignOutUser>>,
+) -> impl Responder {
+    return service::sign_out(pool, token_revocation, authenticated_user, sign_out_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/sign-out-all",
+    responses(
+        (status = 200, description = "User signed out from all sessions successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[post(
+    "/sign-out-all",
+    wrap = "RateLimit::per_user()",
+    wrap = "JWTAuthentication"
+)]
+pub async fn sign_out_all(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+) -> impl Responder {
+    return service::sign_out_all(pool, token_revocation, authenticated_user).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me",
+    responses(
+        (status = 200, description = "Profile of the signed in user"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[get("")]
+pub async fn get_profile(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> impl Responder {
+    return service::get_profile(pool, authenticated_user).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me/email",
+    request_body = ChangeEmail,
+    responses(
+        (status = 200, description = "Email changed successfully, a verification email is sent to the new address"),
+        (status = 400, description = "Invalid email or unchanged email", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Current password is incorrect", body = ErrorResponse),
+        (status = 409, description = "Email already exist", body = ErrorResponse),
+    ),
+)]
+#[patch("/email")]
+pub async fn change_email(
+    pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
+    authenticated_user: AuthenticatedUser,
+    change_email_dto: web::Json<ChangeEmail>,
+) -> impl Responder {
+    return service::change_email(pool, mailer, authenticated_user, change_email_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me/password",
+    request_body = ChangePassword,
+    responses(
+        (status = 200, description = "Password changed successfully, all sessions and personal access tokens are revoked"),
+        (status = 400, description = "Invalid password", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Current password is incorrect", body = ErrorResponse),
+    ),
+)]
+#[put("/password")]
+pub async fn change_password(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    change_password_dto: web::Json<ChangePassword>,
+) -> impl Responder {
+    return service::change_password(
+        pool,
+        token_revocation,
+        authenticated_user,
+        change_password_dto,
+    )
+    .await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me",
+    request_body = DeleteAccount,
+    responses(
+        (status = 200, description = "Account deleted together with all of its todos, projects and tags"),
+        (status = 400, description = "Missing password", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 403, description = "Current password is incorrect", body = ErrorResponse),
+    ),
+)]
+#[delete("")]
+pub async fn delete_account(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    delete_account_dto: web::Json<DeleteAccount>,
+) -> impl Responder {
+    return service::delete_account(
+        pool,
+        token_revocation,
+        authenticated_user,
+        delete_account_dto,
+    )
+    .await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me/tokens",
+    responses(
+        (status = 200, description = "Active personal access tokens of the user, without the tokens themselves"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[get("/tokens")]
+pub async fn get_personal_access_tokens(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> impl Responder {
+    return service::get_personal_access_tokens(pool, authenticated_user).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me/tokens",
+    request_body = CreatePersonalAccessToken,
+    responses(
+        (status = 201, description = "Personal access token created, the token is only shown in this response"),
+        (status = 400, description = "Invalid name, scopes or expiry", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[post("/tokens")]
+pub async fn create_personal_access_token(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    create_token_dto: web::Json<CreatePersonalAccessToken>,
+) -> impl Responder {
+    return service::create_personal_access_token(pool, authenticated_user, create_token_dto).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me/tokens/{id}",
+    params(("id", description = "Unique storage id of the personal access token")),
+    responses(
+        (status = 200, description = "Personal access token revoked successfully"),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+        (status = 404, description = "Personal access token not found", body = ErrorResponse),
+    ),
+)]
+#[delete("/tokens/{id}")]
+pub async fn revoke_personal_access_token(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> impl Responder {
+    return service::revoke_personal_access_token(pool, authenticated_user, path).await;
+}
+
+#[utoipa::path(
+    tag = "Users",
+    path = "/api/users/me/sessions-history",
+    params(GetLoginAttemptsQueryParam),
+    responses(
+        (status = 200, description = "Sign-in attempts of the user, newest first"),
+        (status = 400, description = "Invalid pagination", body = ErrorResponse),
+        (status = 401, description = "Missing, invalid or revoked JWT token", body = ErrorResponse),
+    ),
+)]
+#[get("/sessions-history")]
+pub async fn get_sessions_history(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    query: web::Query<GetLoginAttemptsQueryParam>,
 ) -> impl Responder {
-    return service::login_user(pool, login_user_dto).await;
+    return service::get_sessions_history(pool, authenticated_user, query).await;
 }
@@ -2,8 +2,10 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{types::Uuid, FromRow};
 use utoipa::{IntoParams, ToSchema};
-use validator::Validate;
-#[derive(Clone, Debug, Serialize)]
+use validator::{Validate, ValidationError};
+
+use crate::config::personal_access_token::SCOPES;
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathUuid {
     pub id: Uuid,
 }
@@ -15,25 +17,51 @@ pub struct User {
     pub password: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Failed sign-ins since the last successful one or the last lockout
+    pub failed_login_attempts: i32,
+    /// Lockouts since the last successful sign-in
+    pub lockouts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub role: String,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+/// Role of a user as embedded in access tokens.
+#[derive(Clone, Debug, FromRow)]
+pub struct UserRole {
+    pub role: String,
+    pub disabled_at: Option<DateTime<Utc>>,
 }
 
 impl User {
-    pub fn to_login_success(&self, token: String) -> LoginUserSuccess {
+    pub fn is_locked(&self) -> bool {
+        return self
+            .locked_until
+            .is_some_and(|locked_until| locked_until > Utc::now());
+    }
+
+    pub fn to_login_success(&self, token: String, refresh_token: String) -> LoginUserSuccess {
         return LoginUserSuccess {
             id: self.id,
             email: self.email.clone(),
             created_at: self.created_at,
             updated_at: self.updated_at,
+            email_verified_at: self.email_verified_at,
+            role: self.role.clone(),
             token,
+            refresh_token,
         };
     }
 
-    pub fn to_register_success(&self) -> RegisterUserSuccess {
-        return RegisterUserSuccess {
+    pub fn to_profile(&self) -> UserProfile {
+        return UserProfile {
             id: self.id,
             email: self.email.clone(),
             created_at: self.created_at,
             updated_at: self.updated_at,
+            email_verified_at: self.email_verified_at,
+            role: self.role.clone(),
         };
     }
 }
@@ -46,17 +74,41 @@ pub struct LoginUserSuccess {
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    #[serde(rename = "emailVerifiedAt")]
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub role: String,
+    pub token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+}
+
+#[derive(Clone, Debug, FromRow)]
+pub struct UserRefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RefreshTokenSuccess {
     pub token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
 }
 
 #[derive(Clone, Debug, Serialize)]
-pub struct RegisterUserSuccess {
+pub struct UserProfile {
     pub id: Uuid,
     pub email: String,
     #[serde(rename = "createdAt")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: DateTime<Utc>,
+    #[serde(rename = "emailVerifiedAt")]
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub role: String,
 }
 
 #[derive(Clone, Debug, Validate, Deserialize, ToSchema, IntoParams)]
@@ -78,3 +130,188 @@ pub struct LoginUser {
     #[schema(value_type = String, min_length = 8)]
     pub password: String,
 }
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct RefreshToken {
+    #[serde(rename = "refreshToken")]
+    #[validate(length(min = 1, message = "refresh token must not be empty"))]
+    pub refresh_token: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct VerifyEmail {
+    /// Token from the verification email
+    #[validate(length(min = 1, message = "token must not be empty"))]
+    pub token: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct ResendVerificationEmail {
+    #[validate(email(message = "invalid email address"))]
+    #[schema(value_type = String, format = "email")]
+    pub email: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct ForgotPassword {
+    #[validate(email(message = "invalid email address"))]
+    #[schema(value_type = String, format = "email")]
+    pub email: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct ResetPassword {
+    /// Token from the password reset email
+    #[validate(length(min = 1, message = "token must not be empty"))]
+    pub token: String,
+    #[validate(length(min = 8, message = "password must have at least 8 characters"))]
+    #[schema(value_type = String, min_length = 8)]
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct ChangeEmail {
+    #[validate(email(message = "invalid email address"))]
+    #[schema(value_type = String, format = "email")]
+    pub email: String,
+    /// Current password of the user
+    #[validate(length(min = 1, message = "password must not be empty"))]
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct ChangePassword {
+    #[serde(rename = "currentPassword")]
+    #[validate(length(min = 1, message = "current password must not be empty"))]
+    pub current_password: String,
+    #[serde(rename = "newPassword")]
+    #[validate(length(min = 8, message = "password must have at least 8 characters"))]
+    #[schema(value_type = String, min_length = 8)]
+    pub new_password: String,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct DeleteAccount {
+    /// Current password of the user
+    #[validate(length(min = 1, message = "password must not be empty"))]
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Deserialize, ToSchema)]
+pub struct SignOutUser {
+    /// Refresh token issued alongside the access token, revoked together with it
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct LoginAttempt {
+    pub id: Uuid,
+    #[serde(rename = "ipAddress")]
+    pub ip_address: Option<String>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    pub succeeded: bool,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GetLoginAttemptsSuccess {
+    pub attempts: Vec<LoginAttempt>,
+    pub total: i64,
+    pub page: i64,
+    #[serde(rename = "perPage")]
+    pub per_page: i64,
+    #[serde(rename = "totalPages")]
+    pub total_pages: i64,
+}
+
+const fn limit_default() -> i64 {
+    const LIMIT_DEFAULT: i64 = 10;
+    return LIMIT_DEFAULT;
+}
+const fn offset_default() -> i64 {
+    const OFFSET_DEFAULT: i64 = 0;
+    return OFFSET_DEFAULT;
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams, Validate)]
+pub struct GetLoginAttemptsQueryParam {
+    #[validate(range(min = 1, max = 100, message = "limit must be between 1 and 100"))]
+    #[serde(default = "limit_default")]
+    pub limit: i64,
+    #[validate(range(min = 0, message = "offset must not be negative"))]
+    #[serde(default = "offset_default")]
+    pub offset: i64,
+}
+
+#[derive(Clone, Debug, Validate, Deserialize, ToSchema)]
+pub struct CreatePersonalAccessToken {
+    /// Reminder of what the token is used for
+    #[validate(
+        length(
+            min = 1,
+            max = 100,
+            message = "name must be between 1 and 100 characters"
+        ),
+        custom = "validate_not_blank"
+    )]
+    #[schema(min_length = 1, max_length = 100)]
+    pub name: String,
+    /// Any of todos:read, todos:write, projects:read, projects:write,
+    /// tags:read and tags:write. A write scope also allows reading
+    #[validate(custom = "validate_scopes")]
+    pub scopes: Vec<String>,
+    /// Omit for a token that never expires
+    #[serde(rename = "expiresAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, FromRow, Serialize)]
+pub struct PersonalAccessTokenInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Returned once on creation, the token itself cannot be shown again.
+#[derive(Clone, Debug, Serialize)]
+pub struct CreatePersonalAccessTokenSuccess {
+    #[serde(flatten)]
+    pub info: PersonalAccessTokenInfo,
+    pub token: String,
+}
+
+fn validate_not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        let mut error = ValidationError::new("blank");
+        error.message = Some("must not be blank".into());
+        return Err(error);
+    }
+    return Ok(());
+}
+
+fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if scopes.is_empty() {
+        let mut error = ValidationError::new("empty");
+        error.message = Some("at least one scope is required".into());
+        return Err(error);
+    }
+    if let Some(scope) = scopes
+        .iter()
+        .find(|scope| !SCOPES.contains(&scope.as_str()))
+    {
+        let mut error = ValidationError::new("scope");
+        error.message = Some(format!("unknown scope {}", scope).into());
+        return Err(error);
+    }
+    return Ok(());
+}
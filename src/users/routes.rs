@@ -1,9 +1,37 @@
-use super::controllers::{login_user, register_user};
+use crate::config::{jwt::JWTAuthentication, rate_limit::RateLimit};
+
+use super::controllers::{
+    change_email, change_password, create_personal_access_token, delete_account, forgot_password,
+    get_personal_access_tokens, get_profile, get_sessions_history, login_user, refresh_token,
+    register_user, resend_verification_email, reset_password, revoke_personal_access_token,
+    sign_out, sign_out_all, verify_email,
+};
 use actix_web::web;
 
 pub fn scoped_config(service_config: &mut web::ServiceConfig) {
+    let me_scope = web::scope("/me")
+        .wrap(RateLimit::per_user())
+        .wrap(JWTAuthentication {})
+        .service(get_profile)
+        .service(change_email)
+        .service(change_password)
+        .service(delete_account)
+        .service(get_personal_access_tokens)
+        .service(create_personal_access_token)
+        .service(revoke_personal_access_token)
+        .service(get_sessions_history);
+    // Routes outside of `/me` pick their own rate limit, per IP address
+    // before sign-in and per user after it.
     let user_scope = web::scope("/users")
         .service(register_user)
-        .service(login_user);
+        .service(verify_email)
+        .service(resend_verification_email)
+        .service(forgot_password)
+        .service(reset_password)
+        .service(login_user)
+        .service(refresh_token)
+        .service(sign_out)
+        .service(sign_out_all)
+        .service(me_scope);
     service_config.service(user_scope);
 }
@@ -1,45 +1,49 @@
-use actix_web::{http::StatusCode, web, HttpResponse, Responder};
+use actix_web::{http::StatusCode, web, HttpResponse};
+use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
-use sqlx::{Pool, Postgres};
-use tracing::{error, info};
+use sqlx::{PgExecutor, Pool, Postgres};
+use tracing::{error, info, warn};
+use uuid::Uuid;
 use validator::Validate;
 
 use crate::{
-    config::{argon2::Argon2PasswordHash, jwt::JWT},
-    users::dtos::User,
+    config::{
+        argon2::Argon2PasswordHash,
+        authenticated_user::AuthenticatedUser,
+        client_info::ClientInfo,
+        environment::EnvironmentVariables,
+        jwt::JWT,
+        mailer::{Email, Mailer},
+        opaque_token::OpaqueToken,
+        personal_access_token::PersonalAccessToken,
+        signed_token::SignedToken,
+        token_revocation::TokenRevocation,
+    },
+    error::AppError,
+    projects::service::ensure_inbox,
+    users::dtos::{RefreshTokenSuccess, User, UserRefreshToken, UserRole},
 };
 
-use super::dtos::{LoginUser, RegisterUser};
+use super::dtos::{
+    ChangeEmail, ChangePassword, CreatePersonalAccessToken, CreatePersonalAccessTokenSuccess,
+    DeleteAccount, ForgotPassword, GetLoginAttemptsQueryParam, GetLoginAttemptsSuccess,
+    LoginAttempt, LoginUser, PathUuid, PersonalAccessTokenInfo, RefreshToken, RegisterUser,
+    ResendVerificationEmail, ResetPassword, SignOutUser, VerifyEmail,
+};
+
+const EMAIL_VERIFICATION: &str = "email_verification";
 
 pub async fn register_user(
     pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
     register_user_dto: web::Json<RegisterUser>,
-) -> impl Responder {
-    let validation = register_user_dto.validate();
-    if let Err(error) = validation {
-        let json_error = json!({
-            "errors": error,
-            "statusCode": StatusCode::BAD_REQUEST.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        error!("{}", error);
-        return HttpResponse::BadRequest().json(json_error);
-    }
+) -> Result<HttpResponse, AppError> {
+    register_user_dto.validate()?;
 
     let pool = pool.as_ref();
-    let hashed_password = Argon2PasswordHash::hash_password(register_user_dto.password.to_owned());
-
-    if let Err(error) = hashed_password {
-        let json_error = json!({
-            "message": "internal server error",
-            "statusCode": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        error!("{}", error);
-        return HttpResponse::InternalServerError().json(json_error);
-    }
-    let hashed_password = hashed_password.unwrap();
+    let hashed_password = Argon2PasswordHash::hash_password(register_user_dto.password.to_owned())?;
 
+    let mut transaction = pool.begin().await?;
     let user = sqlx::query_as!(
         User,
         r#"
@@ -50,113 +54,1093 @@ pub async fn register_user(
         register_user_dto.email,
         hashed_password
     )
-    .fetch_one(pool)
-    .await;
-
-    match user {
-        Ok(user) => {
-            let json_user = json!({
-                "data": user.to_register_success(),
-                "message": "user registered successfully",
-                "statusCode": StatusCode::CREATED.as_u16(),
-            });
-            info!("{}", serde_json::to_string(&json_user).unwrap());
-            return HttpResponse::Created().json(json_user);
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(|error| match AppError::from(error) {
+        AppError::Conflict(_) => AppError::Conflict(String::from("email already exist")),
+        error => error,
+    })?;
+    ensure_inbox(&mut *transaction, user.id).await?;
+    transaction.commit().await?;
+    // The account exists either way, a lost email can be sent again.
+    if let Err(error) = send_verification_email(pool, mailer.as_ref(), &user).await {
+        error!(
+            "failed to send verification email to user {}: {}",
+            user.id, error
+        );
+    }
+
+    let json_user = json!({
+        "data": user.to_profile(),
+        "message": "user registered successfully",
+        "statusCode": StatusCode::CREATED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_user).unwrap());
+    return Ok(HttpResponse::Created().json(json_user));
+}
+
+/// Emails the user a link to verify their address. Tokens sent earlier stop
+/// working, only the latest one can be used.
+async fn send_verification_email(
+    pool: &Pool<Postgres>,
+    mailer: &dyn Mailer,
+    user: &User,
+) -> Result<(), AppError> {
+    let environment_variables = EnvironmentVariables::initialize();
+    let lifetime = environment_variables.email_verification_token_lifetime_hours;
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM email_verification_tokens
+        WHERE user_id = $1
+        "#,
+        user.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    let token_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO email_verification_tokens (user_id, expires_at)
+        VALUES ($1, $2)
+        RETURNING id
+        "#,
+        user.id,
+        Utc::now() + Duration::hours(lifetime)
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    let token = SignedToken::sign(EMAIL_VERIFICATION, token_id);
+    let instructions = match environment_variables.email_verification_url {
+        Some(url) => format!("open {}?token={}", url, token),
+        None => format!("use this verification token: {}", token),
+    };
+    let email = Email {
+        to: user.email.clone(),
+        subject: String::from("Verify your email address"),
+        body: format!(
+            "To verify your email address, {}\n\nThe token expires in {} hours.",
+            instructions, lifetime
+        ),
+    };
+    return mailer.send(email).await;
+}
+
+pub async fn verify_email(
+    pool: web::Data<Pool<Postgres>>,
+    verify_email_dto: web::Json<VerifyEmail>,
+) -> Result<HttpResponse, AppError> {
+    verify_email_dto.validate()?;
+    let invalid_token =
+        || AppError::BadRequest(String::from("invalid or expired verification token"));
+    let token_id = SignedToken::verify(EMAIL_VERIFICATION, &verify_email_dto.token)
+        .ok_or_else(invalid_token)?;
+
+    let pool = pool.as_ref();
+    let mut transaction = pool.begin().await?;
+    let user_id = sqlx::query_scalar!(
+        r#"
+        UPDATE email_verification_tokens
+        SET used_at = NOW()
+        WHERE id = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+        token_id
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(invalid_token)?;
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET email_verified_at = COALESCE(email_verified_at, NOW()), updated_at = NOW()
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    let json_response = json!({
+        "message": "email verified successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("user {} verified their email", user_id);
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+pub async fn resend_verification_email(
+    pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
+    resend_verification_email_dto: web::Json<ResendVerificationEmail>,
+) -> Result<HttpResponse, AppError> {
+    resend_verification_email_dto.validate()?;
+    let pool = pool.as_ref();
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE email = $1 AND email_verified_at IS NULL
+        "#,
+        resend_verification_email_dto.email
+    )
+    .fetch_optional(pool)
+    .await?;
+    // Answer the same either way so that the endpoint does not reveal which
+    // addresses have an account.
+    if let Some(user) = user {
+        if let Err(error) = send_verification_email(pool, mailer.as_ref(), &user).await {
+            error!(
+                "failed to send verification email to user {}: {}",
+                user.id, error
+            );
         }
-        Err(error) => {
-            let database_error = error.as_database_error();
-            if let Some(database_error) = database_error {
-                if database_error.is_unique_violation() {
-                    let json_error = json!({
-                        "message": "email already exist",
-                        "statusCode": StatusCode::CONFLICT.as_u16(),
-                    });
-                    error!("{}", serde_json::to_string(&json_error).unwrap());
-                    error!("{}", error);
-                    return HttpResponse::Conflict().json(json_error);
-                }
-            }
-            let json_error = json!({
-                "message": "internal server error",
-                "statusCode": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            error!("{}", error);
-            return HttpResponse::InternalServerError().json(json_error);
+    }
+
+    let json_response = json!({
+        "message": "verification email sent if the account exists and is not verified yet",
+        "statusCode": StatusCode::ACCEPTED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Accepted().json(json_response));
+}
+
+pub async fn forgot_password(
+    pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
+    forgot_password_dto: web::Json<ForgotPassword>,
+) -> Result<HttpResponse, AppError> {
+    forgot_password_dto.validate()?;
+    let pool = pool.as_ref();
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE email = $1
+        "#,
+        forgot_password_dto.email
+    )
+    .fetch_optional(pool)
+    .await?;
+    // Answer the same either way so that the endpoint does not reveal which
+    // addresses have an account.
+    if let Some(user) = user {
+        if let Err(error) = send_password_reset_email(pool, mailer.as_ref(), &user).await {
+            error!(
+                "failed to send password reset email to user {}: {}",
+                user.id, error
+            );
         }
     }
+
+    let json_response = json!({
+        "message": "password reset email sent if the account exists",
+        "statusCode": StatusCode::ACCEPTED.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Accepted().json(json_response));
+}
+
+/// Emails the user a password reset token. Only a hash of the token is
+/// stored and tokens sent earlier stop working.
+async fn send_password_reset_email(
+    pool: &Pool<Postgres>,
+    mailer: &dyn Mailer,
+    user: &User,
+) -> Result<(), AppError> {
+    let environment_variables = EnvironmentVariables::initialize();
+    let lifetime = environment_variables.password_reset_token_lifetime_minutes;
+    let token = OpaqueToken::generate();
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM password_reset_tokens
+        WHERE user_id = $1
+        "#,
+        user.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        "#,
+        user.id,
+        OpaqueToken::hash(&token),
+        Utc::now() + Duration::minutes(lifetime)
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+
+    let instructions = match environment_variables.password_reset_url {
+        Some(url) => format!("open {}?token={}", url, token),
+        None => format!("use this password reset token: {}", token),
+    };
+    let email = Email {
+        to: user.email.clone(),
+        subject: String::from("Reset your password"),
+        body: format!(
+            "To choose a new password, {}\n\nThe token expires in {} minutes. \
+            If you did not ask for a password reset, you can ignore this email.",
+            instructions, lifetime
+        ),
+    };
+    return mailer.send(email).await;
+}
+
+pub async fn reset_password(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    reset_password_dto: web::Json<ResetPassword>,
+) -> Result<HttpResponse, AppError> {
+    reset_password_dto.validate()?;
+    let pool = pool.as_ref();
+    let hashed_password =
+        Argon2PasswordHash::hash_password(reset_password_dto.password.to_owned())?;
+
+    let mut transaction = pool.begin().await?;
+    let user_id = sqlx::query_scalar!(
+        r#"
+        UPDATE password_reset_tokens
+        SET used_at = NOW()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id
+        "#,
+        OpaqueToken::hash(&reset_password_dto.token)
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    .ok_or_else(|| AppError::BadRequest(String::from("invalid or expired password reset token")))?;
+    // Whoever had the old password may have created tokens with it.
+    revoke_personal_access_tokens(&mut *transaction, user_id).await?;
+    // Proving access to the mailbox also lifts a lockout.
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password = $2, failed_login_attempts = 0, locked_until = NULL, updated_at = NOW()
+        WHERE id = $1
+        "#,
+        user_id,
+        hashed_password
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    revoke_all_sessions(pool, token_revocation.as_ref(), user_id).await?;
+
+    let json_response = json!({
+        "message": "password reset successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("user {} reset their password", user_id);
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+async fn find_user_role<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<UserRole, AppError> {
+    return Ok(sqlx::query_as!(
+        UserRole,
+        r#"
+        SELECT role, disabled_at
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(executor)
+    .await?);
+}
+
+fn issue_access_token(user_id: Uuid, user_role: UserRole) -> Result<String, AppError> {
+    return JWT::jwt_encode(user_id.to_string(), user_role.role).map_err(AppError::internal);
+}
+
+fn account_disabled() -> AppError {
+    return AppError::Forbidden(String::from("account has been disabled"));
+}
+
+fn invalid_credentials() -> AppError {
+    return AppError::Unauthorized(String::from("invalid credentials"));
+}
+
+fn account_locked(locked_until: DateTime<Utc>) -> AppError {
+    return AppError::Locked(format!(
+        "account is locked after too many failed sign-in attempts, try again after {}",
+        locked_until.to_rfc3339()
+    ));
+}
+
+/// Records a sign-in attempt, without a user when no account has the email.
+async fn record_login_attempt<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Option<Uuid>,
+    email: &str,
+    client_info: &ClientInfo,
+    succeeded: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO login_attempts (user_id, email, ip_address, user_agent, succeeded)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        user_id,
+        email,
+        client_info.ip_address,
+        client_info.user_agent,
+        succeeded
+    )
+    .execute(executor)
+    .await?;
+    return Ok(());
+}
+
+/// How long the lockout following `previous_lockouts` earlier ones lasts:
+/// `minutes` doubled for every earlier lockout, capped at `max_minutes`.
+fn lockout_duration(previous_lockouts: i32, minutes: i64, max_minutes: i64) -> Duration {
+    let multiplier = 1_i64 << previous_lockouts.clamp(0, 30);
+    return Duration::minutes(minutes.saturating_mul(multiplier).min(max_minutes));
+}
+
+/// Counts a failed sign-in against the user and locks the account once
+/// `LOGIN_LOCKOUT_THRESHOLD` sign-ins failed in a row. Every lockout lasts twice
+/// as long as the previous one, up to `LOGIN_LOCKOUT_MAX_MINUTES`. Returns the
+/// end of the lockout when this failure started one.
+async fn record_failed_login(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let environment_variables = EnvironmentVariables::initialize();
+    let mut transaction = pool.begin().await?;
+    let record = sqlx::query!(
+        r#"
+        UPDATE users
+        SET failed_login_attempts = CASE
+                WHEN failed_login_attempts + 1 >= $2 THEN 0
+                ELSE failed_login_attempts + 1
+            END,
+            lockouts = CASE
+                WHEN failed_login_attempts + 1 >= $2 THEN lockouts + 1
+                ELSE lockouts
+            END
+        WHERE id = $1
+        RETURNING failed_login_attempts, lockouts
+        "#,
+        user_id,
+        environment_variables.login_lockout_threshold.max(1)
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    if record.failed_login_attempts > 0 {
+        transaction.commit().await?;
+        return Ok(None);
+    }
+
+    let locked_until = Utc::now()
+        + lockout_duration(
+            record.lockouts - 1,
+            environment_variables.login_lockout_minutes,
+            environment_variables.login_lockout_max_minutes,
+        );
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET locked_until = $2
+        WHERE id = $1
+        "#,
+        user_id,
+        locked_until
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    return Ok(Some(locked_until));
 }
 
 pub async fn login_user(
     pool: web::Data<Pool<Postgres>>,
     login_user_dto: web::Json<LoginUser>,
-) -> impl Responder {
-    let validation = login_user_dto.validate();
-    if let Err(error) = validation {
-        let json_error = json!({
-            "errors": error,
-            "statusCode": StatusCode::BAD_REQUEST.as_u16(),
-        });
-        error!("{}", serde_json::to_string(&json_error).unwrap());
-        error!("{}", error);
-        return HttpResponse::BadRequest().json(json_error);
-    }
+    client_info: ClientInfo,
+) -> Result<HttpResponse, AppError> {
+    login_user_dto.validate()?;
     let pool = pool.as_ref();
+    let email = login_user_dto.email.as_str();
     let user = sqlx::query_as!(
         User,
         r#"
         SELECT * FROM users
         WHERE email = $1
         "#,
-        login_user_dto.email
+        email
     )
-    .fetch_one(pool)
-    .await;
-
-    match user {
-        Ok(user) => {
-            if Argon2PasswordHash::verify_password(
-                login_user_dto.password.clone(),
-                user.password.clone(),
-            ) {
-                let token = JWT::jwt_encode(user.id.to_string());
-
-                match token {
-                    Ok(token) => {
-                        let json_user = json!({
-                            "data": user.to_login_success(token),
-                            "message": "user logged in successfully",
-                            "statusCode": StatusCode::OK.as_u16(),
-                        });
-                        info!("{}", serde_json::to_string(&json_user).unwrap());
-                        return HttpResponse::Ok().json(json_user);
-                    }
-                    Err(error) => {
-                        let json_error = json!({
-                            "message": "internal server error",
-                            "statusCode": StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
-                        });
-                        error!("{}", serde_json::to_string(&json_error).unwrap());
-                        error!("{}", error);
-                        return HttpResponse::InternalServerError().json(json_error);
-                    }
-                }
+    .fetch_optional(pool)
+    .await?;
+    let Some(user) = user else {
+        record_login_attempt(pool, None, email, &client_info, false).await?;
+        return Err(invalid_credentials());
+    };
+
+    // The lock is only revealed to callers who know the password, anyone else
+    // gets the same answer as for an unknown email. Failures while locked don't
+    // count towards the next lockout.
+    let locked_until = user.locked_until.filter(|_| user.is_locked());
+    if !Argon2PasswordHash::verify_password(login_user_dto.password.clone(), user.password.clone())
+    {
+        record_login_attempt(pool, Some(user.id), email, &client_info, false).await?;
+        if locked_until.is_none() {
+            if let Some(locked_until) = record_failed_login(pool, user.id).await? {
+                warn!("user {} locked out until {}", user.id, locked_until);
             }
-            let json_error = json!({
-                "message": "invalid credentials",
-                "statusCode": StatusCode::UNAUTHORIZED.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            return HttpResponse::Unauthorized().json(json_error);
-        }
-        Err(error) => {
-            let json_error = json!({
-                "message": "invalid credentials",
-                "statusCode": StatusCode::UNAUTHORIZED.as_u16(),
-            });
-            error!("{}", serde_json::to_string(&json_error).unwrap());
-            error!("{}", error);
-            return HttpResponse::Unauthorized().json(json_error);
         }
+        return Err(invalid_credentials());
+    }
+
+    if let Some(locked_until) = locked_until {
+        record_login_attempt(pool, Some(user.id), email, &client_info, false).await?;
+        return Err(account_locked(locked_until));
+    }
+
+    if user.disabled_at.is_some() {
+        record_login_attempt(pool, Some(user.id), email, &client_info, false).await?;
+        return Err(account_disabled());
+    }
+
+    let environment_variables = EnvironmentVariables::initialize();
+    if environment_variables.require_email_verification && user.email_verified_at.is_none() {
+        record_login_attempt(pool, Some(user.id), email, &client_info, false).await?;
+        return Err(AppError::Forbidden(String::from(
+            "email address has not been verified",
+        )));
+    }
+
+    let mut transaction = pool.begin().await?;
+    if user.failed_login_attempts > 0 || user.lockouts > 0 {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = 0, lockouts = 0, locked_until = NULL
+            WHERE id = $1
+            "#,
+            user.id
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    record_login_attempt(&mut *transaction, Some(user.id), email, &client_info, true).await?;
+    let user_role = find_user_role(&mut *transaction, user.id).await?;
+    let token = issue_access_token(user.id, user_role)?;
+    let (_, refresh_token) = issue_refresh_token(&mut *transaction, user.id, None).await?;
+    transaction.commit().await?;
+
+    let json_user = json!({
+        "data": user.to_login_success(token, refresh_token),
+        "message": "user logged in successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("user {} logged in", user.id);
+    return Ok(HttpResponse::Ok().json(json_user));
+}
+
+pub async fn get_profile(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let user = find_user(pool.as_ref(), authenticated_user.id).await?;
+
+    let json_response = json!({
+        "data": user.to_profile(),
+        "message": "user profile fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+/// Moves the account to a new address, which has to be verified again.
+pub async fn change_email(
+    pool: web::Data<Pool<Postgres>>,
+    mailer: web::Data<dyn Mailer>,
+    authenticated_user: AuthenticatedUser,
+    change_email_dto: web::Json<ChangeEmail>,
+) -> Result<HttpResponse, AppError> {
+    change_email_dto.validate()?;
+    let pool = pool.as_ref();
+    let user = find_user(pool, authenticated_user.id).await?;
+    check_current_password(&change_email_dto.password, &user)?;
+    if user.email == change_email_dto.email {
+        return Err(AppError::BadRequest(String::from(
+            "email must differ from the current one",
+        )));
+    }
+
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users
+        SET email = $2, email_verified_at = NULL, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+        "#,
+        user.id,
+        change_email_dto.email
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|error| match AppError::from(error) {
+        AppError::Conflict(_) => AppError::Conflict(String::from("email already exist")),
+        error => error,
+    })?;
+    if let Err(error) = send_verification_email(pool, mailer.as_ref(), &user).await {
+        error!(
+            "failed to send verification email to user {}: {}",
+            user.id, error
+        );
+    }
+
+    let json_response = json!({
+        "data": user.to_profile(),
+        "message": "email changed successfully, verify the new address",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+/// Changes the password and signs the user out everywhere, including the
+/// session making the request.
+pub async fn change_password(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    change_password_dto: web::Json<ChangePassword>,
+) -> Result<HttpResponse, AppError> {
+    change_password_dto.validate()?;
+    let pool = pool.as_ref();
+    let user = find_user(pool, authenticated_user.id).await?;
+    check_current_password(&change_password_dto.current_password, &user)?;
+    let hashed_password =
+        Argon2PasswordHash::hash_password(change_password_dto.new_password.to_owned())?;
+
+    let mut transaction = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        UPDATE users
+        SET password = $2, updated_at = NOW()
+        WHERE id = $1
+        "#,
+        user.id,
+        hashed_password
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // Whoever had the old password may have created tokens with it.
+    revoke_personal_access_tokens(&mut *transaction, user.id).await?;
+    transaction.commit().await?;
+    revoke_all_sessions(pool, token_revocation.as_ref(), user.id).await?;
+
+    let json_response = json!({
+        "message": "password changed successfully, sign in again",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("user {} changed their password", user.id);
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+/// Deletes the account. Todos, projects, tags and every other row owned by the
+/// user go with it through `ON DELETE CASCADE`.
+pub async fn delete_account(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    delete_account_dto: web::Json<DeleteAccount>,
+) -> Result<HttpResponse, AppError> {
+    delete_account_dto.validate()?;
+    let pool = pool.as_ref();
+    let user = find_user(pool, authenticated_user.id).await?;
+    check_current_password(&delete_account_dto.password, &user)?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM users
+        WHERE id = $1
+        "#,
+        user.id
+    )
+    .execute(pool)
+    .await?;
+    token_revocation.forget_user(user.id);
+
+    let json_response = json!({
+        "message": "account deleted successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("user {} deleted their account", user.id);
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+pub async fn get_personal_access_tokens(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let tokens = sqlx::query_as!(
+        PersonalAccessTokenInfo,
+        r#"
+        SELECT id, name, scopes, expires_at, last_used_at, created_at
+        FROM personal_access_tokens
+        WHERE user_id = $1 AND revoked_at IS NULL
+        ORDER BY created_at DESC, id DESC
+        "#,
+        authenticated_user.id
+    )
+    .fetch_all(pool.as_ref())
+    .await?;
+
+    let json_tokens = json!({
+        "data": tokens,
+        "message": "personal access tokens fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_tokens).unwrap());
+    return Ok(HttpResponse::Ok().json(json_tokens));
+}
+
+pub async fn create_personal_access_token(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    create_token_dto: web::Json<CreatePersonalAccessToken>,
+) -> Result<HttpResponse, AppError> {
+    create_token_dto.validate()?;
+    if create_token_dto
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(AppError::BadRequest(String::from(
+            "expiresAt must be in the future",
+        )));
+    }
+    let mut scopes = create_token_dto.scopes.clone();
+    scopes.sort();
+    scopes.dedup();
+
+    let token = PersonalAccessToken::generate();
+    let info = sqlx::query_as!(
+        PersonalAccessTokenInfo,
+        r#"
+        INSERT INTO personal_access_tokens (user_id, name, token_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id, name, scopes, expires_at, last_used_at, created_at
+        "#,
+        authenticated_user.id,
+        create_token_dto.name.trim(),
+        OpaqueToken::hash(&token),
+        &scopes,
+        create_token_dto.expires_at
+    )
+    .fetch_one(pool.as_ref())
+    .await?;
+
+    info!(
+        "user {} created personal access token {}",
+        authenticated_user.id, info.id
+    );
+    // The token is left out of the log on purpose.
+    let json_token = json!({
+        "data": CreatePersonalAccessTokenSuccess { info, token },
+        "message": "personal access token created successfully, it will not be shown again",
+        "statusCode": StatusCode::CREATED.as_u16(),
+    });
+    return Ok(HttpResponse::Created().json(json_token));
+}
+
+pub async fn revoke_personal_access_token(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    path: web::Path<PathUuid>,
+) -> Result<HttpResponse, AppError> {
+    let token_id = path.id;
+    let query_result = sqlx::query!(
+        r#"
+        UPDATE personal_access_tokens
+        SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+        "#,
+        token_id,
+        authenticated_user.id
+    )
+    .execute(pool.as_ref())
+    .await?;
+    if query_result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!(
+            "personal access token with id {} not found",
+            token_id
+        )));
+    }
+
+    let json_response = json!({
+        "message": "personal access token revoked successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+async fn find_user(pool: &Pool<Postgres>, user_id: Uuid) -> Result<User, AppError> {
+    return sqlx::query_as!(
+        User,
+        r#"
+        SELECT * FROM users
+        WHERE id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(String::from("user not found")));
+}
+
+fn check_current_password(password: &str, user: &User) -> Result<(), AppError> {
+    if !Argon2PasswordHash::verify_password(password.to_owned(), user.password.clone()) {
+        return Err(AppError::Forbidden(String::from(
+            "current password is incorrect",
+        )));
+    }
+    return Ok(());
+}
+
+pub async fn get_sessions_history(
+    pool: web::Data<Pool<Postgres>>,
+    authenticated_user: AuthenticatedUser,
+    query: web::Query<GetLoginAttemptsQueryParam>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM login_attempts
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+    let attempts = sqlx::query_as!(
+        LoginAttempt,
+        r#"
+        SELECT id, ip_address, user_agent, succeeded, created_at
+        FROM login_attempts
+        WHERE user_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2
+        OFFSET $3
+        "#,
+        user_id,
+        query.limit,
+        query.offset
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let data = GetLoginAttemptsSuccess {
+        attempts,
+        total,
+        page: (query.offset / query.limit) + 1,
+        per_page: query.limit,
+        total_pages: (total + query.limit - 1) / query.limit,
+    };
+    let json_attempts = json!({
+        "data": data,
+        "message": "sessions history fetched successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_attempts).unwrap());
+    return Ok(HttpResponse::Ok().json(json_attempts));
+}
+
+async fn issue_refresh_token<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+    family_id: Option<Uuid>,
+) -> Result<(Uuid, String), sqlx::Error> {
+    let environment_variables = EnvironmentVariables::initialize();
+    let lifetime = environment_variables.refresh_token_lifetime_days;
+    let refresh_token = OpaqueToken::generate();
+    let expires_at = Utc::now() + Duration::days(lifetime);
+    let record = sqlx::query!(
+        r#"
+        INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+        VALUES ($1, COALESCE($2, gen_random_uuid()), $3, $4)
+        RETURNING id
+        "#,
+        user_id,
+        family_id,
+        OpaqueToken::hash(refresh_token.as_str()),
+        expires_at
+    )
+    .fetch_one(executor)
+    .await?;
+    return Ok((record.id, refresh_token));
+}
+
+async fn revoke_refresh_token_family<'e, E: PgExecutor<'e>>(
+    executor: E,
+    family_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let query_result = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE family_id = $1 AND revoked_at IS NULL
+        "#,
+        family_id
+    )
+    .execute(executor)
+    .await?;
+    return Ok(query_result.rows_affected());
+}
+
+/// Revokes `stored` and issues its successor in the same family. Returns `None`
+/// when the token was already rotated by a concurrent request.
+async fn rotate_refresh_token(
+    pool: &Pool<Postgres>,
+    stored: &UserRefreshToken,
+) -> Result<Option<String>, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let rows_affected = sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE id = $1 AND revoked_at IS NULL
+        "#,
+        stored.id
+    )
+    .execute(&mut *transaction)
+    .await?
+    .rows_affected();
+    if rows_affected == 0 {
+        transaction.rollback().await?;
+        return Ok(None);
+    }
+    let (replacement_id, refresh_token) =
+        issue_refresh_token(&mut *transaction, stored.user_id, Some(stored.family_id)).await?;
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET replaced_by = $1
+        WHERE id = $2
+        "#,
+        replacement_id,
+        stored.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    return Ok(Some(refresh_token));
+}
+
+pub async fn refresh_token(
+    pool: web::Data<Pool<Postgres>>,
+    refresh_token_dto: web::Json<RefreshToken>,
+) -> Result<HttpResponse, AppError> {
+    refresh_token_dto.validate()?;
+    let pool = pool.as_ref();
+    let token_hash = OpaqueToken::hash(refresh_token_dto.refresh_token.as_str());
+    let stored = sqlx::query_as!(
+        UserRefreshToken,
+        r#"
+        SELECT id, user_id, family_id, expires_at, revoked_at
+        FROM refresh_tokens
+        WHERE token_hash = $1
+        "#,
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::Unauthorized(String::from("invalid refresh token")))?;
+
+    if stored.expires_at <= Utc::now() {
+        return Err(AppError::Unauthorized(String::from(
+            "refresh token expired",
+        )));
+    }
+
+    let user_role = find_user_role(pool, stored.user_id).await?;
+    if user_role.disabled_at.is_some() {
+        return Err(account_disabled());
+    }
+
+    let rotated = match stored.revoked_at {
+        Some(_) => None,
+        None => rotate_refresh_token(pool, &stored).await?,
+    };
+
+    let Some(refresh_token) = rotated else {
+        warn!(
+            "refresh token reuse detected for user {}, revoking family {}",
+            stored.user_id, stored.family_id
+        );
+        revoke_refresh_token_family(pool, stored.family_id).await?;
+        return Err(AppError::Unauthorized(String::from(
+            "refresh token reuse detected",
+        )));
+    };
+
+    let token = issue_access_token(stored.user_id, user_role)?;
+    let json_token = json!({
+        "data": RefreshTokenSuccess { token, refresh_token },
+        "message": "token refreshed successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("token refreshed for user {}", stored.user_id);
+    return Ok(HttpResponse::Ok().json(json_token));
+}
+
+pub async fn sign_out(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+    sign_out_dto: Option<web::Json<SignOutUser>>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+
+    token_revocation
+        .revoke_token(
+            pool,
+            authenticated_user.jti,
+            user_id,
+            authenticated_user.exp,
+        )
+        .await?;
+
+    let refresh_token =
+        sign_out_dto.and_then(|sign_out_dto| sign_out_dto.into_inner().refresh_token);
+    if let Some(refresh_token) = refresh_token {
+        sqlx::query!(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = NOW()
+            WHERE family_id = (
+                SELECT family_id FROM refresh_tokens
+                WHERE token_hash = $1 AND user_id = $2
+            ) AND revoked_at IS NULL
+            "#,
+            OpaqueToken::hash(refresh_token.as_str()),
+            user_id
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    let json_response = json!({
+        "message": "user signed out successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+pub async fn sign_out_all(
+    pool: web::Data<Pool<Postgres>>,
+    token_revocation: web::Data<TokenRevocation>,
+    authenticated_user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let user_id = authenticated_user.id;
+    let pool = pool.as_ref();
+
+    revoke_all_sessions(pool, token_revocation.as_ref(), user_id).await?;
+    token_revocation
+        .revoke_token(
+            pool,
+            authenticated_user.jti,
+            user_id,
+            authenticated_user.exp,
+        )
+        .await?;
+
+    let json_response = json!({
+        "message": "user signed out from all sessions successfully",
+        "statusCode": StatusCode::OK.as_u16(),
+    });
+    info!("{}", serde_json::to_string(&json_response).unwrap());
+    return Ok(HttpResponse::Ok().json(json_response));
+}
+
+/// Revokes every personal access token of the user that is still active.
+async fn revoke_personal_access_tokens<'e, E: PgExecutor<'e>>(
+    executor: E,
+    user_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE personal_access_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(executor)
+    .await?;
+    return Ok(());
+}
+
+/// Revokes every access token issued to the user so far together with all of
+/// their refresh tokens.
+pub async fn revoke_all_sessions(
+    pool: &Pool<Postgres>,
+    token_revocation: &TokenRevocation,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    token_revocation.revoke_user_tokens(pool, user_id).await?;
+    sqlx::query!(
+        r#"
+        UPDATE refresh_tokens
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::lockout_duration;
+
+    #[test]
+    fn first_lockout_lasts_the_base_minutes() {
+        assert_eq!(lockout_duration(0, 15, 1440), Duration::minutes(15));
+    }
+
+    #[test]
+    fn every_lockout_doubles_the_previous_one() {
+        assert_eq!(lockout_duration(1, 15, 1440), Duration::minutes(30));
+        assert_eq!(lockout_duration(2, 15, 1440), Duration::minutes(60));
+        assert_eq!(lockout_duration(6, 15, 1440), Duration::minutes(960));
+    }
+
+    #[test]
+    fn lockouts_are_capped_at_the_max_minutes() {
+        assert_eq!(lockout_duration(7, 15, 1440), Duration::minutes(1440));
+        assert_eq!(lockout_duration(30, 15, 1440), Duration::minutes(1440));
+        assert_eq!(lockout_duration(0, 15, 10), Duration::minutes(10));
+    }
+
+    #[test]
+    fn huge_lockout_counts_do_not_overflow() {
+        assert_eq!(
+            lockout_duration(i32::MAX, 15, 1440),
+            Duration::minutes(1440)
+        );
+        assert_eq!(lockout_duration(-1, 15, 1440), Duration::minutes(15));
     }
 }